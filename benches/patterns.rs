@@ -0,0 +1,317 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! Benchmark of the pattern matchers over the included dictionaries.
+//!
+//! The tries of parsed and compiled dictionaries are compared with the hash
+//! map probing used before, kept below. Run with ``cargo bench``, or
+//! ``cargo bench -- de_DE`` for a single dictionary.
+
+use std::collections::HashSet;
+use std::fs;
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pyphen_rs::HyphDict;
+
+/// The pattern matcher used before tries, probing a hash map with every
+/// substring of the word.
+mod hash_map {
+    use std::collections::HashMap;
+    use std::mem;
+
+    use pyphen_rs::DataInt;
+
+    /// A single level of hyphenation patterns.
+    struct Patterns {
+        patterns: HashMap<String, (usize, Vec<DataInt>)>,
+        maxlen: usize,
+    }
+
+    impl Patterns {
+        fn new(patterns: HashMap<String, (usize, Vec<DataInt>)>) -> Self {
+            let maxlen = patterns
+                .keys()
+                .map(|pattern| pattern.chars().count())
+                .max()
+                .unwrap_or(0);
+
+            Self { patterns, maxlen }
+        }
+
+        fn points(&self, word: &str) -> Vec<DataInt> {
+            if self.patterns.is_empty() {
+                return Vec::new();
+            }
+
+            let pointed_word = format!(".{}.", word);
+            let offsets: Vec<_> = pointed_word
+                .char_indices()
+                .map(|(offset, _)| offset)
+                .chain(Some(pointed_word.len()))
+                .collect();
+            let length = offsets.len() - 1;
+            let mut references = vec![DataInt::new(0, None); length + 1];
+
+            for i in 0..(length - 1) {
+                for j in (i + 1)..=(i + self.maxlen).min(length) {
+                    let pattern = self.patterns.get(&pointed_word[offsets[i]..offsets[j]]);
+                    if let Some(pattern) = pattern {
+                        let (offset, ref values) = *pattern;
+                        // values of malformed patterns can go past the word
+                        let references = references.iter_mut().skip(i + offset);
+                        for (x, y) in references.zip(values.iter()) {
+                            if y.value > x.value {
+                                *x = y.clone();
+                            }
+                        }
+                    }
+                }
+            }
+
+            references
+                .into_iter()
+                .enumerate()
+                .filter(|(i, reference)| *i > 1 && *i < length - 1 && reference.value % 2 != 0)
+                .map(|(i, reference)| DataInt::with_ref(i - 1, &reference))
+                .collect()
+        }
+    }
+
+    /// Hyphenation patterns of both levels.
+    pub struct HyphDict {
+        compound: Patterns,
+        patterns: Patterns,
+    }
+
+    impl HyphDict {
+        pub fn new(content: &str) -> Self {
+            let mut patterns = HashMap::new();
+            let mut compound = None;
+            for line in content.lines() {
+                if line.is_empty() || line.starts_with('%') || line.starts_with('#') {
+                    continue;
+                }
+                if line.contains("HYPHENMIN") || line.starts_with("NOHYPHEN") {
+                    continue;
+                }
+                if line.starts_with("NEXTLEVEL") {
+                    compound = Some(mem::take(&mut patterns));
+                    continue;
+                }
+                if let Some((tags, values)) = parse_pattern(line) {
+                    patterns.insert(tags, values);
+                }
+            }
+            let compound = compound.unwrap_or_else(|| {
+                ["1-1", "1'1", "1\u{2013}1", "1\u{2019}1"]
+                    .iter()
+                    .filter_map(|pattern| parse_pattern(pattern))
+                    .collect()
+            });
+
+            Self {
+                compound: Patterns::new(compound),
+                patterns: Patterns::new(patterns),
+            }
+        }
+
+        pub fn positions(&self, word: &str) -> Vec<DataInt> {
+            self.hyphenate(&word.to_lowercase())
+        }
+
+        fn hyphenate(&self, word: &str) -> Vec<DataInt> {
+            let boundaries = self.compound.points(word);
+            if boundaries.is_empty() {
+                return self.patterns.points(word);
+            }
+
+            let offsets: Vec<_> = word
+                .char_indices()
+                .map(|(offset, _)| offset)
+                .chain(Some(word.len()))
+                .collect();
+            let mut points = Vec::new();
+            let mut begin = 0;
+            for boundary in boundaries {
+                let end = boundary.value;
+                points.extend(
+                    self.patterns
+                        .points(&word[offsets[begin]..offsets[end]])
+                        .iter()
+                        .map(|point| DataInt::with_ref(begin + point.value, point)),
+                );
+                points.push(DataInt {
+                    compound: true,
+                    ..boundary
+                });
+                begin = end;
+            }
+            points.extend(
+                self.patterns
+                    .points(&word[offsets[begin]..])
+                    .iter()
+                    .map(|point| DataInt::with_ref(begin + point.value, point)),
+            );
+
+            points
+        }
+    }
+
+    /// Parse the letters and the values of a pattern, ignoring nonstandard
+    /// hyphenation.
+    fn parse_pattern(pattern: &str) -> Option<(String, (usize, Vec<DataInt>))> {
+        let pattern = pattern.split('/').next().unwrap();
+        let mut chars = Vec::new();
+        let mut rest = pattern;
+        while let Some(c) = rest.chars().next() {
+            match u8::from_str_radix(rest.get(2..4).unwrap_or(""), 16) {
+                Ok(byte) if rest.starts_with("^^") => {
+                    chars.push(byte as char);
+                    rest = &rest[4..];
+                }
+                _ => {
+                    chars.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+
+        // like the regex used by the parser, consecutive digits give
+        // consecutive values
+        let mut tags = String::new();
+        let mut values = vec![0];
+        let mut digit = false;
+        for c in chars {
+            match c.to_digit(10) {
+                Some(value) if digit => values.push(value as usize),
+                Some(value) => *values.last_mut().unwrap() = value as usize,
+                None => {
+                    tags.push(c);
+                    values.push(0);
+                }
+            }
+            digit = c.is_ascii_digit();
+        }
+
+        let start = values.iter().position(|&v| v != 0)?;
+        let end = values.iter().rposition(|&v| v != 0).unwrap() + 1;
+        let values = values[start..end]
+            .iter()
+            .map(|&value| DataInt::new(value, None))
+            .collect();
+
+        Some((tags, (start, values)))
+    }
+}
+
+/// Get the included dictionaries, without duplicates.
+fn dictionaries() -> Vec<(String, String)> {
+    let mut filenames: Vec<_> = fs::read_dir("dictionaries")
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_str().unwrap().to_string())
+        .collect();
+    filenames.sort();
+
+    let mut contents = HashSet::new();
+    filenames
+        .into_iter()
+        .filter_map(|filename| {
+            let content = fs::read_to_string(&filename).unwrap();
+            let lang = filename
+                .trim_start_matches("dictionaries/hyph_")
+                .trim_end_matches(".dic")
+                .to_string();
+            Some((lang, content.clone())).filter(|_| contents.insert(content))
+        })
+        .collect()
+}
+
+/// Get words made of the letters of consecutive patterns of a dictionary,
+/// followed by compound words made of pairs of these words.
+fn words(content: &str) -> Vec<String> {
+    let letters: Vec<String> = content
+        .lines()
+        .filter(|line| !line.starts_with('%') && !line.starts_with('#'))
+        .filter(|line| !line.contains(char::is_whitespace))
+        .map(|line| {
+            line.split('/')
+                .next()
+                .unwrap()
+                .chars()
+                .filter(|c| c.is_alphabetic())
+                .collect()
+        })
+        .filter(|letters: &String| !letters.is_empty())
+        .collect();
+    let step = (letters.len() / 2000).max(1);
+
+    let words: Vec<_> = letters
+        .windows(3)
+        .step_by(step)
+        .map(|letters| letters.concat())
+        .collect();
+    let compounds: Vec<_> = words.chunks(4).map(|words| words.join("-")).collect();
+
+    words.into_iter().chain(compounds).collect()
+}
+
+fn positions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("positions");
+    group
+        .sample_size(20)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2));
+
+    for (lang, content) in dictionaries() {
+        let words = words(&content);
+        let hash_map = hash_map::HyphDict::new(&content);
+        let trie = HyphDict::read(content.as_bytes(), &lang).unwrap();
+        let compiled = HyphDict::from_compiled(trie.compile(), &lang).unwrap();
+        for word in &words {
+            let expected: Vec<_> = hash_map.positions(word).iter().map(|i| i.value).collect();
+            let values: Vec<_> = trie.positions(word).iter().map(|i| i.value).collect();
+            assert_eq!(values, expected, "{} {}", lang, word);
+        }
+
+        group.throughput(Throughput::Elements(words.len() as u64));
+        group.bench_function(BenchmarkId::new("hash map", &lang), |b| {
+            b.iter(|| {
+                for word in &words {
+                    black_box(hash_map.positions(word));
+                }
+            })
+        });
+        group.bench_function(BenchmarkId::new("trie", &lang), |b| {
+            b.iter(|| {
+                for word in &words {
+                    black_box(trie.positions(word));
+                }
+            })
+        });
+        group.bench_function(BenchmarkId::new("compiled", &lang), |b| {
+            b.iter(|| {
+                for word in &words {
+                    black_box(compiled.positions(word));
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, positions);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! Generate the list of dictionaries embedded in the crate.
+//!
+//! Each ``lang-*`` feature embeds the dictionaries of a language, for all its
+//! regions and scripts, and ``all-languages`` embeds all of them. Identical
+//! files are only embedded once.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=dictionaries");
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let all = env::var_os("CARGO_FEATURE_ALL_LANGUAGES").is_some();
+    let mut languages = Vec::new();
+    for entry in fs::read_dir("dictionaries").unwrap() {
+        let filename = entry.unwrap().file_name().into_string().unwrap();
+        if !filename.starts_with("hyph_") || !filename.ends_with(".dic") {
+            continue;
+        }
+
+        let lang = filename
+            .trim_start_matches("hyph_")
+            .trim_end_matches(".dic")
+            .to_string();
+        let feature = format!(
+            "CARGO_FEATURE_LANG_{}",
+            lang.split('_').next().unwrap().to_uppercase()
+        );
+        if all || env::var_os(feature).is_some() {
+            languages.push((lang, filename));
+        }
+    }
+    languages.sort();
+
+    let mut contents = HashMap::new();
+    let mut statics = String::new();
+    let mut entries = String::new();
+    for (lang, filename) in &languages {
+        let content = fs::read(Path::new("dictionaries").join(filename)).unwrap();
+        let count = contents.len();
+        let index = *contents.entry(content).or_insert_with(|| {
+            statics += &format!(
+                "static DIC_{}: &str = include_str!(concat!(env!(\"CARGO_MANIFEST_DIR\"), \
+                 \"/dictionaries/{}\"));\n",
+                count, filename
+            );
+            count
+        });
+        entries += &format!("    ({:?}, DIC_{}),\n", lang, index);
+    }
+
+    let code = format!(
+        "{}\n/// Embedded dictionaries, sorted by language.\n\
+         pub static EMBEDDED: &[(&str, &str)] = &[\n{}];\n",
+        statics, entries
+    );
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("embedded.rs");
+    fs::write(out, code).unwrap();
+}
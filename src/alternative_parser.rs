@@ -13,7 +13,7 @@
 // FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
 // details.
 
-use std::rc::Rc;
+use std::sync::Arc;
 
 use super::DataInt;
 
@@ -22,25 +22,39 @@ use super::DataInt;
 /// The instance returns a special int with data about the current position in
 /// the pattern when called with an odd value.
 pub struct AlternativeParser {
-    change: Rc<String>,
+    change: Arc<String>,
     index: isize,
     cut: usize,
 }
 
 impl AlternativeParser {
-    pub fn new(pattern: &str, alternative: &str) -> Self {
+    /// Returns ``None`` if the alternative is not a valid ``change,index,cut``
+    /// triple, or if its index and cut do not select letters of the pattern.
+    pub fn new(pattern: &str, alternative: &str) -> Option<Self> {
         let alternative: Vec<_> = alternative.split(',').collect();
+        if alternative.len() != 3 || !alternative[0].contains('=') {
+            return None;
+        }
         let mut ap = Self {
-            change: Rc::new(alternative[0].to_string()),
-            index: alternative[1].parse().unwrap(),
-            cut: alternative[2].parse().unwrap(),
+            change: Arc::new(alternative[0].to_string()),
+            index: alternative[1].parse().ok()?,
+            cut: alternative[2].parse().ok()?,
         };
 
+        // the change replaces letters of the pattern, dots excluded
+        let letters = pattern
+            .chars()
+            .filter(|&c| c != '.' && !c.is_ascii_digit())
+            .count();
+        if ap.index < 1 || ap.index as usize - 1 + ap.cut > letters {
+            return None;
+        }
+
         if pattern.starts_with('.') {
             ap.index += 1;
         }
 
-        ap
+        Some(ap)
     }
 
     pub fn call(&mut self, d_int: &DataInt) -> DataInt {
@@ -52,9 +66,9 @@ impl AlternativeParser {
                 cut,
             } = *self;
 
-            DataInt::new(d_int.value, Some((Rc::clone(change), index, cut)))
+            DataInt::new(d_int.value, Some((Arc::clone(change), index, cut)))
         } else {
             DataInt::new(d_int.value, None)
         }
     }
-}
\ No newline at end of file
+}
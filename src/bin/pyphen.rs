@@ -0,0 +1,251 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! Command-line interface hyphenating words, files or the standard input.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process;
+use std::sync::Arc;
+
+use pyphen_rs::{languages, words, Builder, Pyphen};
+
+const USAGE: &str = "\
+Usage: pyphen [OPTIONS] [WORD]...
+
+Hyphenate the given words, or the text of the given files or of the standard
+input, keeping everything but the words as it is.
+
+Options:
+  -l, --lang LANG          language of the included dictionary [default: en_US]
+  -d, --dictionary FILE    dictionary file to use instead of a language
+      --left N             minimum number of characters in the first syllable
+      --right N            minimum number of characters in the last syllable
+      --hyphen STRING      string inserted at the hyphenation points [default: -]
+  -p, --positions          print each word with its hyphenation positions
+  -i, --iterate            print each way to split each word, the longest first
+  -f, --file FILE          hyphenate the text of the file, - for the standard input
+      --list               list the available languages
+  -h, --help               print this help
+";
+
+/// What is printed for each word.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// The text, with hyphens inserted in the words.
+    Inserted,
+    /// A line for each word, with its positions.
+    Positions,
+    /// A line for each split of each word.
+    Iterate,
+}
+
+/// Options given on the command line.
+struct Options {
+    lang: String,
+    dictionary: Option<String>,
+    left: Option<usize>,
+    right: Option<usize>,
+    hyphen: String,
+    output: Output,
+    files: Vec<String>,
+    words: Vec<String>,
+    list: bool,
+}
+
+impl Options {
+    /// Parse the arguments, without the name of the program.
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut options = Self {
+            lang: "en_US".to_string(),
+            dictionary: None,
+            left: None,
+            right: None,
+            hyphen: "-".to_string(),
+            output: Output::Inserted,
+            files: Vec::new(),
+            words: Vec::new(),
+            list: false,
+        };
+
+        while let Some(arg) = args.next() {
+            // accept both --option=value and --option value
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+                _ => (arg.clone(), None),
+            };
+            let mut value = || match inline {
+                Some(value) => Ok(value.to_string()),
+                None => args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", name)),
+            };
+            let number = |value: String| {
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid number {:?} for {}", value, name))
+            };
+
+            match name.as_str() {
+                "-l" | "--lang" => options.lang = value()?,
+                "-d" | "--dictionary" => options.dictionary = Some(value()?),
+                "--left" => options.left = Some(number(value()?)?),
+                "--right" => options.right = Some(number(value()?)?),
+                "--hyphen" => options.hyphen = value()?,
+                "-p" | "--positions" => options.output = Output::Positions,
+                "-i" | "--iterate" => options.output = Output::Iterate,
+                "-f" | "--file" => options.files.push(value()?),
+                "--list" => options.list = true,
+                "-h" | "--help" => {
+                    print!("{}", USAGE);
+                    process::exit(0);
+                }
+                "--" => options.words.extend(args.by_ref()),
+                "-" => options.files.push(arg),
+                name if name.starts_with('-') => return Err(format!("unknown option {}", name)),
+                _ => options.words.push(arg),
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Create the hyphenation instance.
+    fn pyphen(&self) -> Result<Pyphen, String> {
+        let mut builder = match &self.dictionary {
+            Some(filename) => Builder::filename(Arc::new(filename.clone())),
+            None => Builder::lang(&self.lang),
+        };
+        if let Some(left) = self.left {
+            builder.left(left);
+        }
+        if let Some(right) = self.right {
+            builder.right(right);
+        }
+
+        builder.build().map_err(|error| error.to_string())
+    }
+}
+
+/// Write a word as requested by the options.
+fn word<W: Write>(output: &mut W, dic: &Pyphen, options: &Options, word: &str) -> io::Result<()> {
+    match options.output {
+        Output::Inserted => write!(output, "{}", dic.inserted_with(word, &options.hyphen)),
+        Output::Positions => {
+            let positions: Vec<_> = dic
+                .positions(word)
+                .iter()
+                .map(|position| position.value.to_string())
+                .collect();
+            writeln!(output, "{}\t{}", word, positions.join(" "))
+        }
+        Output::Iterate => {
+            for (start, end) in dic.iterate(word) {
+                writeln!(output, "{}{}\t{}", start, options.hyphen, end)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Hyphenate the words of a text, keeping the rest of the text if the
+/// hyphens are inserted.
+fn text<W: Write>(output: &mut W, dic: &Pyphen, options: &Options, text: &str) -> io::Result<()> {
+    if options.output == Output::Inserted {
+        return write!(output, "{}", dic.inserted_text_with(text, &options.hyphen));
+    }
+    for (_, w) in words(text) {
+        word(output, dic, options, w)?;
+    }
+
+    Ok(())
+}
+
+/// Hyphenate the lines of a stream.
+fn stream<R: BufRead, W: Write>(
+    mut input: R,
+    output: &mut W,
+    dic: &Pyphen,
+    options: &Options,
+) -> io::Result<()> {
+    let mut line = Vec::new();
+    while input.read_until(b'\n', &mut line)? > 0 {
+        let string = std::str::from_utf8(&line)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        text(output, dic, options, string)?;
+        output.flush()?;
+        line.clear();
+    }
+
+    Ok(())
+}
+
+/// Run the command.
+fn run() -> Result<(), Box<dyn Error>> {
+    let options = Options::parse(std::env::args().skip(1))
+        .map_err(|error| format!("{} (see --help)", error))?;
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+
+    if options.list {
+        for lang in languages() {
+            writeln!(output, "{}", lang)?;
+        }
+        return Ok(());
+    }
+
+    let dic = options.pyphen()?;
+    if !options.words.is_empty() {
+        for w in &options.words {
+            word(&mut output, &dic, &options, w)?;
+            if options.output == Output::Inserted {
+                writeln!(output)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let files = match options.files.is_empty() {
+        true => vec!["-".to_string()],
+        false => options.files.clone(),
+    };
+    for filename in files {
+        let result = match filename.as_str() {
+            "-" => stream(io::stdin().lock(), &mut output, &dic, &options),
+            _ => File::open(&filename)
+                .and_then(|file| stream(BufReader::new(file), &mut output, &dic, &options)),
+        };
+        match result {
+            Err(error) if error.kind() != io::ErrorKind::BrokenPipe => {
+                return Err(format!("{}: {}", filename, error).into())
+            }
+            result => result?,
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run() {
+        // stop quietly when the output is closed, as by head
+        let kind = error.downcast_ref::<io::Error>().map(io::Error::kind);
+        if kind != Some(io::ErrorKind::BrokenPipe) {
+            eprintln!("pyphen: {}", error);
+            process::exit(1);
+        }
+    }
+}
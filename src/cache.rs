@@ -0,0 +1,239 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::DataInt;
+
+/// Maximum number of independently locked parts of the cache.
+const SHARDS: usize = 16;
+
+/// Minimum number of entries of each part of a bounded cache.
+const SHARD_ENTRIES: usize = 64;
+
+/// Minimum number of bytes of each part of a bounded cache.
+const SHARD_BYTES: usize = 16 * 1024;
+
+/// Policy of the cache of hyphenation positions kept by ``Pyphen``.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Positions are computed for each word.
+    Disabled,
+    /// Positions of all the words are kept.
+    #[default]
+    Unbounded,
+    /// Positions of at most the given number of words are kept, the least
+    /// recently used words are removed first.
+    MaxEntries(usize),
+    /// Positions are kept while their approximate size in memory is below
+    /// the given number of bytes, the least recently used words are removed
+    /// first.
+    MaxBytes(usize),
+}
+
+/// Statistics of the cache of hyphenation positions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of words found in the cache.
+    pub hits: u64,
+    /// Number of words not found in the cache.
+    pub misses: u64,
+    /// Number of words removed from the cache to respect its bounds.
+    pub evictions: u64,
+    /// Number of words in the cache.
+    pub entries: usize,
+    /// Approximate size in memory of the words in the cache, in bytes.
+    pub bytes: usize,
+}
+
+/// Positions of a word stored in the cache.
+struct Entry {
+    points: Arc<Vec<DataInt>>,
+    used: u64,
+    size: usize,
+}
+
+/// Independently locked part of the cache.
+#[derive(Default)]
+struct Shard {
+    entries: HashMap<String, Entry>,
+    /// Words by time of last use, only kept for bounded caches.
+    order: BTreeMap<u64, String>,
+    clock: u64,
+    bytes: usize,
+    /// Maximum number of entries or bytes, ``None`` if unbounded.
+    limit: Option<usize>,
+}
+
+/// Cache of hyphenation positions, shared between threads.
+///
+/// Words are spread over shards with their own lock, so that threads
+/// hyphenating different words seldom wait for each other. Bounded caches
+/// remove the least recently used words of each shard.
+pub struct Cache {
+    policy: CachePolicy,
+    shards: Vec<Mutex<Shard>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl Cache {
+    /// Create an empty cache.
+    pub fn new(policy: CachePolicy) -> Self {
+        // small bounded caches have less shards, to stay close to a real LRU
+        let (count, limit) = match policy {
+            CachePolicy::Disabled => (1, Some(0)),
+            CachePolicy::Unbounded => (SHARDS, None),
+            CachePolicy::MaxEntries(limit) => {
+                ((limit / SHARD_ENTRIES).clamp(1, SHARDS), Some(limit))
+            }
+            CachePolicy::MaxBytes(limit) => ((limit / SHARD_BYTES).clamp(1, SHARDS), Some(limit)),
+        };
+        let shards = (0..count)
+            .map(|index| {
+                // spread the limit over the shards
+                let limit = limit.map(|limit| limit / count + usize::from(index < limit % count));
+                Mutex::new(Shard {
+                    limit,
+                    ..Shard::default()
+                })
+            })
+            .collect();
+
+        Self {
+            policy,
+            shards,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Get the cached positions of a word.
+    pub fn get(&self, word: &str) -> Option<Arc<Vec<DataInt>>> {
+        let mut shard = self.shard(word);
+        let Shard {
+            ref mut entries,
+            ref mut order,
+            ref mut clock,
+            limit,
+            ..
+        } = *shard;
+
+        let entry = match entries.get_mut(word) {
+            Some(entry) => entry,
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+
+        // mark the word as recently used
+        if limit.is_some() {
+            *clock += 1;
+            let word = order.remove(&entry.used).unwrap();
+            order.insert(*clock, word);
+            entry.used = *clock;
+        }
+
+        Some(Arc::clone(&entry.points))
+    }
+
+    /// Store the positions of a word.
+    pub fn insert(&self, word: String, points: Arc<Vec<DataInt>>) {
+        let size = word.len()
+            + mem::size_of::<(String, Entry)>()
+            + points.len() * mem::size_of::<DataInt>();
+        let weight = match self.policy {
+            CachePolicy::MaxBytes(_) => size,
+            _ => 1,
+        };
+
+        let mut shard = self.shard(&word);
+        let shard = &mut *shard;
+        if shard.limit.is_some_and(|limit| weight > limit) || shard.entries.contains_key(&word) {
+            return;
+        }
+
+        // remove the least recently used words to make room
+        if let Some(limit) = shard.limit {
+            loop {
+                let used = match self.policy {
+                    CachePolicy::MaxBytes(_) => shard.bytes,
+                    _ => shard.entries.len(),
+                };
+                if used + weight <= limit {
+                    break;
+                }
+                let (_, oldest) = shard.order.pop_first().unwrap();
+                let entry = shard.entries.remove(&oldest).unwrap();
+                shard.bytes -= entry.size;
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        shard.clock += 1;
+        if shard.limit.is_some() {
+            shard.order.insert(shard.clock, word.clone());
+        }
+        shard.bytes += size;
+        let used = shard.clock;
+        shard.entries.insert(word, Entry { points, used, size });
+    }
+
+    /// Remove all the words from the cache.
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            let mut shard = shard.lock().unwrap_or_else(PoisonError::into_inner);
+            shard.entries.clear();
+            shard.order.clear();
+            shard.bytes = 0;
+        }
+    }
+
+    /// Get the statistics of the cache.
+    pub fn stats(&self) -> CacheStats {
+        let mut stats = CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            ..CacheStats::default()
+        };
+        for shard in &self.shards {
+            let shard = shard.lock().unwrap_or_else(PoisonError::into_inner);
+            stats.entries += shard.entries.len();
+            stats.bytes += shard.bytes;
+        }
+
+        stats
+    }
+
+    /// Lock the shard where a word is stored.
+    fn shard(&self, word: &str) -> MutexGuard<'_, Shard> {
+        let mut hasher = DefaultHasher::new();
+        word.hash(&mut hasher);
+        let shard = &self.shards[hasher.finish() as usize % self.shards.len()];
+
+        // the cache is always consistent, even if another thread panicked
+        shard.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,467 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! Compiled dictionaries.
+//!
+//! A compiled dictionary holds the patterns of both levels as tries, laid
+//! out so that words can be hyphenated straight from the bytes, without
+//! parsing the patterns again. All integers are little-endian ``u32``s and
+//! all offsets are counted from the beginning of the data:
+//!
+//! - header: the ``MAGIC`` bytes, the format ``VERSION``, the four
+//!   ``*HYPHENMIN`` directives (``NONE`` if missing), and the offsets of the
+//!   following sections;
+//! - two string lists, for the ``NOHYPHEN`` directive and for the
+//!   ``NOHYPHEN`` strings really used: a count, then the length and the
+//!   UTF-8 bytes of each string;
+//! - the alternatives table: a count, then ``(change offset, change length,
+//!   index, cut)`` for each nonstandard hyphenation;
+//! - the compound and the hyphenation tries: the numbers of nodes, edges
+//!   and values, then the nodes ``(first edge, edge count, first value,
+//!   value count, value offset)``, the edges ``(character, node)`` sorted by
+//!   character, and the values ``(value, alternative or NONE)``;
+//! - a string list of the exceptions, in the ``ta-ble`` notation.
+//!
+//! The root of each trie is its first node.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::str;
+use std::sync::Arc;
+
+use super::patterns::{self, Trie};
+use super::{DataInt, Metadata};
+
+/// Bytes at the beginning of compiled dictionaries.
+pub const MAGIC: &[u8; 8] = b"PYPHENRS";
+
+/// Version of the compiled format.
+pub const VERSION: u32 = 2;
+
+/// Value used for missing numbers.
+const NONE: u32 = u32::MAX;
+
+const HEADER_SIZE: usize = 52;
+const ALTERNATIVE_SIZE: usize = 16;
+const NODE_SIZE: usize = 20;
+const EDGE_SIZE: usize = 8;
+const VALUE_SIZE: usize = 8;
+
+/// Bytes of a compiled dictionary, owned or borrowed for the whole program.
+type Bytes = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
+/// Nonstandard hyphenation data of the alternatives table, shared by both
+/// levels of patterns.
+type Alternatives = Arc<Vec<(Arc<String>, isize, usize)>>;
+
+/// Read the number at the given offset.
+///
+/// The offset must have been checked when the dictionary was loaded.
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    let mut number = [0; 4];
+    number.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(number)
+}
+
+/// Cursor used to check the sections of a compiled dictionary.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], offset: usize) -> Self {
+        Self { bytes, offset }
+    }
+
+    /// Get the next number.
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.slice(4)?;
+        Ok(u32_at(bytes, 0))
+    }
+
+    /// Get the next number as a size or an offset.
+    fn usize(&mut self) -> Result<usize, String> {
+        self.u32().map(|number| number as usize)
+    }
+
+    /// Get the next bytes.
+    fn slice(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(length)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| format!("truncated data at offset {}", self.offset))?;
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+
+        Ok(slice)
+    }
+
+    /// Get the next bytes of a table, and check its size.
+    fn table(&mut self, count: usize, size: usize) -> Result<&'a [u8], String> {
+        let length = count
+            .checked_mul(size)
+            .ok_or_else(|| format!("table too large at offset {}", self.offset))?;
+        self.slice(length)
+    }
+
+    /// Get the next string list.
+    fn strings(&mut self) -> Result<Vec<String>, String> {
+        let count = self.usize()?;
+        (0..count)
+            .map(|_| {
+                let length = self.usize()?;
+                let offset = self.offset;
+                let bytes = self.slice(length)?;
+                str::from_utf8(bytes)
+                    .map(str::to_string)
+                    .map_err(|_| format!("invalid UTF-8 at offset {}", offset))
+            })
+            .collect()
+    }
+}
+
+/// The parts of a compiled dictionary.
+pub struct Compiled {
+    pub metadata: Metadata,
+    pub no_hyphen: Vec<String>,
+    pub compound: Patterns,
+    pub patterns: Patterns,
+    pub exceptions: Vec<String>,
+}
+
+impl Compiled {
+    /// Check the compiled dictionary and find its parts.
+    ///
+    /// Returns `Err` with the reason if the data is not a valid compiled
+    /// dictionary of the current version.
+    pub fn new(bytes: Bytes) -> Result<Self, String> {
+        let data = (*bytes).as_ref();
+        if data.len() < HEADER_SIZE || &data[..MAGIC.len()] != MAGIC {
+            return Err("not a compiled dictionary".to_string());
+        }
+
+        let mut header = Reader::new(data, MAGIC.len());
+        let version = header.u32()?;
+        if version != VERSION {
+            return Err(format!("unsupported version {}", version));
+        }
+
+        let mut minimum = || -> Result<_, String> {
+            let number = header.u32()?;
+            Ok(Some(number as usize).filter(|_| number != NONE))
+        };
+        let mut metadata = Metadata {
+            left_hyphen_min: minimum()?,
+            right_hyphen_min: minimum()?,
+            compound_left_hyphen_min: minimum()?,
+            compound_right_hyphen_min: minimum()?,
+            no_hyphen: Vec::new(),
+        };
+        metadata.no_hyphen = Reader::new(data, header.usize()?).strings()?;
+        let no_hyphen = Reader::new(data, header.usize()?).strings()?;
+
+        let mut reader = Reader::new(data, header.usize()?);
+        let count = reader.usize()?;
+        let alternatives = reader
+            .table(count, ALTERNATIVE_SIZE)?
+            .chunks(ALTERNATIVE_SIZE)
+            .map(|entry| {
+                let offset = u32_at(entry, 0) as usize;
+                let change = Reader::new(data, offset).slice(u32_at(entry, 4) as usize)?;
+                let change = str::from_utf8(change)
+                    .map_err(|_| format!("invalid UTF-8 at offset {}", offset))?;
+                Ok((
+                    Arc::new(change.to_string()),
+                    u32_at(entry, 8) as i32 as isize,
+                    u32_at(entry, 12) as usize,
+                ))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let alternatives = Arc::new(alternatives);
+
+        let compound = Patterns::new(Arc::clone(&bytes), header.usize()?, &alternatives)?;
+        let patterns = Patterns::new(Arc::clone(&bytes), header.usize()?, &alternatives)?;
+        let exceptions = Reader::new(data, header.usize()?).strings()?;
+
+        Ok(Self {
+            metadata,
+            no_hyphen,
+            compound,
+            patterns,
+            exceptions,
+        })
+    }
+}
+
+/// A single level of hyphenation patterns, stored in the bytes of a
+/// compiled dictionary.
+pub struct Patterns {
+    bytes: Bytes,
+    alternatives: Alternatives,
+    nodes: usize,
+    edges: usize,
+    values: usize,
+}
+
+impl Patterns {
+    /// Check the trie stored at the given offset.
+    fn new(bytes: Bytes, offset: usize, alternatives: &Alternatives) -> Result<Self, String> {
+        let data = (*bytes).as_ref();
+
+        let mut reader = Reader::new(data, offset);
+        let node_count = reader.usize()?;
+        let edge_count = reader.usize()?;
+        let value_count = reader.usize()?;
+        let nodes = reader.offset;
+        let node_table = reader.table(node_count, NODE_SIZE)?;
+        let edges = reader.offset;
+        let edge_table = reader.table(edge_count, EDGE_SIZE)?;
+        let values = reader.offset;
+        let value_table = reader.table(value_count, VALUE_SIZE)?;
+
+        if node_count == 0 {
+            return Err(format!("empty trie at offset {}", offset));
+        }
+        for node in node_table.chunks(NODE_SIZE) {
+            let last_edge = (u32_at(node, 0) as usize).saturating_add(u32_at(node, 4) as usize);
+            let last_value = (u32_at(node, 8) as usize).saturating_add(u32_at(node, 12) as usize);
+            if last_edge > edge_count || last_value > value_count {
+                return Err(format!("invalid node in trie at offset {}", offset));
+            }
+        }
+        if edge_table
+            .chunks(EDGE_SIZE)
+            .any(|edge| u32_at(edge, 4) as usize >= node_count)
+        {
+            return Err(format!("invalid edge in trie at offset {}", offset));
+        }
+        if value_table.chunks(VALUE_SIZE).any(|value| {
+            let alternative = u32_at(value, 4);
+            alternative != NONE && alternative as usize >= alternatives.len()
+        }) {
+            return Err(format!("invalid value in trie at offset {}", offset));
+        }
+
+        Ok(Self {
+            bytes,
+            alternatives: Arc::clone(alternatives),
+            nodes,
+            edges,
+            values,
+        })
+    }
+
+    fn data(&self) -> &[u8] {
+        (*self.bytes).as_ref()
+    }
+
+    /// Get the number stored in a field of a node.
+    fn node(&self, node: usize, field: usize) -> usize {
+        u32_at(self.data(), self.nodes + node * NODE_SIZE + field * 4) as usize
+    }
+
+    /// Get the nonstandard hyphenation data of an alternative.
+    fn alternative(&self, index: u32) -> Option<(Arc<String>, isize, usize)> {
+        if index == NONE {
+            return None;
+        }
+
+        // the index has been checked when the dictionary was loaded
+        let (change, index, cut) = &self.alternatives[index as usize];
+        Some((Arc::clone(change), *index, *cut))
+    }
+}
+
+impl Trie for Patterns {
+    fn child(&self, node: usize, c: char) -> Option<usize> {
+        let data = self.data();
+        let first = self.edges + self.node(node, 0) * EDGE_SIZE;
+        let (mut low, mut high) = (0, self.node(node, 1));
+        while low < high {
+            let middle = (low + high) / 2;
+            let edge = first + middle * EDGE_SIZE;
+            match u32_at(data, edge).cmp(&(c as u32)) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return Some(u32_at(data, edge + 4) as usize),
+            }
+        }
+
+        None
+    }
+
+    fn children(&self, node: usize) -> Vec<(char, usize)> {
+        let data = self.data();
+        let first = self.edges + self.node(node, 0) * EDGE_SIZE;
+        (0..self.node(node, 1))
+            .filter_map(|index| {
+                let edge = first + index * EDGE_SIZE;
+                let c = std::char::from_u32(u32_at(data, edge))?;
+                Some((c, u32_at(data, edge + 4) as usize))
+            })
+            .collect()
+    }
+
+    fn values(&self, node: usize) -> (usize, Vec<DataInt>) {
+        let data = self.data();
+        let first = self.values + self.node(node, 2) * VALUE_SIZE;
+        let values = (0..self.node(node, 3))
+            .map(|index| {
+                let value = first + index * VALUE_SIZE;
+                DataInt::new(
+                    u32_at(data, value) as usize,
+                    self.alternative(u32_at(data, value + 4)),
+                )
+            })
+            .collect();
+
+        (self.node(node, 4), values)
+    }
+
+    fn apply(&self, node: usize, start: usize, references: &mut [DataInt]) {
+        let data = self.data();
+        let first = self.values + self.node(node, 2) * VALUE_SIZE;
+        let start = start + self.node(node, 4);
+        let count = self.node(node, 3);
+        for (index, x) in references.iter_mut().skip(start).take(count).enumerate() {
+            let value = first + index * VALUE_SIZE;
+            let y = u32_at(data, value) as usize;
+            if y > x.value {
+                *x = DataInt::new(y, self.alternative(u32_at(data, value + 4)));
+            }
+        }
+    }
+}
+
+/// Writer of compiled dictionaries.
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u32(&mut self, number: usize) {
+        self.bytes.extend_from_slice(&(number as u32).to_le_bytes());
+    }
+
+    /// Write a number at an offset reserved before.
+    fn set_u32(&mut self, offset: usize, number: usize) {
+        self.bytes[offset..offset + 4].copy_from_slice(&(number as u32).to_le_bytes());
+    }
+
+    fn strings(&mut self, strings: &[String]) {
+        self.u32(strings.len());
+        for string in strings {
+            self.u32(string.len());
+            self.bytes.extend_from_slice(string.as_bytes());
+        }
+    }
+
+    /// Write a level of patterns as a trie.
+    fn trie(
+        &mut self,
+        patterns: &patterns::Patterns,
+        alternatives: &HashMap<(&str, isize, usize), usize>,
+    ) {
+        self.u32(patterns.nodes.len());
+        self.u32(patterns.edges.len());
+        self.u32(patterns.values.len());
+        for node in &patterns.nodes {
+            self.u32(node.first_edge);
+            self.u32(node.edge_count);
+            self.u32(node.first_value);
+            self.u32(node.value_count);
+            self.u32(node.offset);
+        }
+        for &(c, node) in &patterns.edges {
+            self.u32(c as usize);
+            self.u32(node);
+        }
+        for value in &patterns.values {
+            self.u32(value.value);
+            match &value.data {
+                Some((change, index, cut)) => {
+                    self.u32(alternatives[&(change.as_str(), *index, *cut)])
+                }
+                None => self.bytes.extend_from_slice(&NONE.to_le_bytes()),
+            }
+        }
+    }
+}
+
+/// Compile the parts of a dictionary.
+pub fn compile(
+    metadata: &Metadata,
+    no_hyphen: &[String],
+    compound: &patterns::Patterns,
+    patterns: &patterns::Patterns,
+    exceptions: &[String],
+) -> Vec<u8> {
+    let mut writer = Writer::default();
+    writer.bytes.extend_from_slice(MAGIC);
+    writer.u32(VERSION as usize);
+    for minimum in &[
+        metadata.left_hyphen_min,
+        metadata.right_hyphen_min,
+        metadata.compound_left_hyphen_min,
+        metadata.compound_right_hyphen_min,
+    ] {
+        writer.u32(minimum.unwrap_or(NONE as usize));
+    }
+    writer.bytes.resize(HEADER_SIZE, 0);
+
+    writer.set_u32(28, writer.bytes.len());
+    writer.strings(&metadata.no_hyphen);
+    writer.set_u32(32, writer.bytes.len());
+    writer.strings(no_hyphen);
+
+    // list the alternatives in a stable order
+    let mut alternatives = Vec::new();
+    let mut indexes = HashMap::new();
+    let values = compound.values.iter().chain(&patterns.values);
+    for (change, index, cut) in values.filter_map(|value| value.data.as_ref()) {
+        let key = (change.as_str(), *index, *cut);
+        indexes.entry(key).or_insert_with(|| {
+            alternatives.push(key);
+            alternatives.len() - 1
+        });
+    }
+    writer.set_u32(36, writer.bytes.len());
+    writer.u32(alternatives.len());
+    let mut change_offset = writer.bytes.len() + alternatives.len() * ALTERNATIVE_SIZE;
+    for (change, index, cut) in &alternatives {
+        writer.u32(change_offset);
+        writer.u32(change.len());
+        writer
+            .bytes
+            .extend_from_slice(&(*index as i32).to_le_bytes());
+        writer.u32(*cut);
+        change_offset += change.len();
+    }
+    for (change, _, _) in &alternatives {
+        writer.bytes.extend_from_slice(change.as_bytes());
+    }
+
+    writer.set_u32(40, writer.bytes.len());
+    writer.trie(compound, &indexes);
+    writer.set_u32(44, writer.bytes.len());
+    writer.trie(patterns, &indexes);
+    writer.set_u32(48, writer.bytes.len());
+    writer.strings(exceptions);
+
+    writer.bytes
+}
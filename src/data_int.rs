@@ -14,19 +14,29 @@
 // details.
 
 use std::fmt;
-use std::rc::Rc;
+use std::sync::Arc;
 
 /// ``int`` with some other data can be stuck to in a ``data`` attribute.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct DataInt {
+    /// The integer value, a hyphenation position counted in characters once
+    /// the word is hyphenated.
     pub value: usize,
-    pub data: Option<(Rc<String>, isize, usize)>,
+    /// Nonstandard hyphenation data ``(change, index, cut)``, if any.
+    pub data: Option<(Arc<String>, isize, usize)>,
+    /// Whether the position is a compound word boundary found by the first
+    /// pattern level of the dictionary.
+    pub compound: bool,
 }
 
 impl DataInt {
     /// Create a new ``DataInt``.
-    pub fn new(value: usize, data: Option<(Rc<String>, isize, usize)>) -> Self {
-        Self { value, data }
+    pub fn new(value: usize, data: Option<(Arc<String>, isize, usize)>) -> Self {
+        Self {
+            value,
+            data,
+            compound: false,
+        }
     }
 
     // Create a new with ``DataInt`` to using the data from another
@@ -35,8 +45,29 @@ impl DataInt {
         Self {
             value,
             data: reference.data.clone(),
+            compound: reference.compound,
         }
     }
+
+    /// Get the hyphenation position as a byte offset in the word.
+    ///
+    /// - *word* - unicode string of the hyphenated word
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::lang("ru_RU").build().unwrap();
+    /// let positions = dic.positions("слово");
+    ///
+    /// assert_eq!(positions[0].value, 3);
+    /// assert_eq!(positions[0].byte_offset("слово"), 6);
+    /// ```
+    pub fn byte_offset(&self, word: &str) -> usize {
+        word.char_indices()
+            .nth(self.value)
+            .map_or(word.len(), |(offset, _)| offset)
+    }
 }
 
 impl fmt::Debug for DataInt {
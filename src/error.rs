@@ -0,0 +1,116 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+/// Error returned when a hyphenation instance cannot be created.
+#[derive(Debug)]
+pub enum Error {
+    /// No included dictionary matches the language.
+    UnknownLanguage {
+        /// The requested language.
+        language: String,
+        /// The languages tried by the fallback algorithm, in order.
+        tried: Vec<String>,
+    },
+    /// The dictionary file does not exist.
+    MissingFile {
+        /// Filename of the dictionary.
+        filename: String,
+    },
+    /// The dictionary file cannot be read.
+    Io {
+        /// Filename of the dictionary.
+        filename: String,
+        /// The underlying I/O error.
+        error: io::Error,
+    },
+    /// A line of the dictionary is not valid in its character set.
+    InvalidEncoding {
+        /// Filename of the dictionary.
+        filename: String,
+        /// Line number, starting at 1.
+        line: usize,
+    },
+    /// The character set declared by the dictionary is not supported.
+    UnsupportedCharset {
+        /// Filename of the dictionary.
+        filename: String,
+        /// The declared character set.
+        charset: String,
+    },
+    /// A pattern of the dictionary cannot be parsed.
+    MalformedPattern {
+        /// Filename of the dictionary.
+        filename: String,
+        /// Line number, starting at 1.
+        line: usize,
+        /// The malformed pattern.
+        pattern: String,
+    },
+    /// The compiled dictionary is invalid or has an unsupported version.
+    InvalidCompiled {
+        /// Filename of the dictionary.
+        filename: String,
+        /// Why the dictionary cannot be loaded.
+        reason: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownLanguage { language, tried } => write!(
+                f,
+                "no dictionary for language {:?} (tried {})",
+                language,
+                tried.join(", ")
+            ),
+            Error::MissingFile { filename } => {
+                write!(f, "dictionary file {:?} does not exist", filename)
+            }
+            Error::Io { filename, error } => {
+                write!(f, "cannot read dictionary file {:?}: {}", filename, error)
+            }
+            Error::InvalidEncoding { filename, line } => {
+                write!(f, "{}:{}: invalid encoding", filename, line)
+            }
+            Error::UnsupportedCharset { filename, charset } => write!(
+                f,
+                "unsupported character set {:?} in dictionary {:?}",
+                charset, filename
+            ),
+            Error::MalformedPattern {
+                filename,
+                line,
+                pattern,
+            } => write!(f, "{}:{}: malformed pattern {:?}", filename, line, pattern),
+            Error::InvalidCompiled { filename, reason } => {
+                write!(f, "invalid compiled dictionary {:?}: {}", filename, reason)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
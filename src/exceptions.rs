@@ -0,0 +1,161 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! Words hyphenated without the patterns of the dictionary.
+//!
+//! Exceptions use the notation of TeX's ``\hyphenation``: the word is written
+//! with a hyphen at each allowed position, as in ``ta-ble``, and a word
+//! without hyphens is never hyphenated.
+
+use std::collections::HashMap;
+use std::io::{BufRead, ErrorKind};
+use std::sync::Arc;
+
+use super::hyph_dict::lowercase;
+use super::{read_file, DataInt, Error};
+
+/// Where exceptions are read from, in the order given to the builder.
+#[derive(Clone, Debug)]
+pub enum ExceptionSource {
+    /// Exceptions given as strings.
+    Words(Vec<String>),
+    /// A file of exceptions, by filename.
+    File(String),
+}
+
+/// Positions of the exceptions, by lowercase word.
+#[derive(Clone, Default)]
+pub struct Exceptions {
+    words: HashMap<String, Arc<Vec<DataInt>>>,
+}
+
+impl Exceptions {
+    /// Read the exceptions of the sources, the last ones overriding the
+    /// first ones.
+    ///
+    /// Returns `Err` if a file cannot be read.
+    pub fn new(sources: &[ExceptionSource]) -> Result<Self, Error> {
+        let mut exceptions = Self::default();
+        for source in sources {
+            match source {
+                ExceptionSource::Words(words) => {
+                    for word in words {
+                        exceptions.insert(word);
+                    }
+                }
+                ExceptionSource::File(filename) => exceptions.read_file(filename)?,
+            }
+        }
+
+        Ok(exceptions)
+    }
+
+    /// Add an exception, such as ``ta-ble``.
+    pub fn insert(&mut self, exception: &str) {
+        let (word, hyphens) = split_hyphens(exception);
+        if !word.is_empty() {
+            let points = hyphens.into_iter().map(|i| DataInt::new(i, None)).collect();
+            self.words.insert(lowercase(&word), Arc::new(points));
+        }
+    }
+
+    /// Add the exceptions of a file, separated by whitespace.
+    ///
+    /// Lines starting with ``%`` or ``#`` are comments.
+    pub fn read_file(&mut self, filename: &str) -> Result<(), Error> {
+        for exception in read_words(filename)? {
+            self.insert(&exception);
+        }
+
+        Ok(())
+    }
+
+    /// Get all the exceptions, sorted, in the ``ta-ble`` notation.
+    pub fn strings(&self) -> Vec<String> {
+        let mut strings: Vec<_> = self
+            .words
+            .iter()
+            .map(|(word, points)| {
+                let mut string = String::new();
+                let mut points = points.iter().map(|point| point.value).peekable();
+                for (index, c) in word.chars().enumerate() {
+                    if points.next_if_eq(&index).is_some() {
+                        string.push('-');
+                    }
+                    string.push(c);
+                }
+                string
+            })
+            .collect();
+        strings.sort();
+
+        strings
+    }
+
+    /// Get the positions of the word, if it is an exception.
+    ///
+    /// Words are matched regardless of their case.
+    pub fn get(&self, word: &str) -> Option<Arc<Vec<DataInt>>> {
+        if self.words.is_empty() {
+            return None;
+        }
+
+        self.words.get(&lowercase(word)).cloned()
+    }
+}
+
+/// Split a hyphenated word, such as ``ta-ble``, into the word and the
+/// positions of its hyphens, in characters.
+pub(crate) fn split_hyphens(hyphenated: &str) -> (String, Vec<usize>) {
+    let mut word = String::new();
+    let mut hyphens = Vec::new();
+    let mut length = 0;
+    for part in hyphenated.split('-').filter(|part| !part.is_empty()) {
+        if length > 0 {
+            hyphens.push(length);
+        }
+        word.push_str(part);
+        length += part.chars().count();
+    }
+
+    (word, hyphens)
+}
+
+/// Read the words of a file, separated by whitespace.
+///
+/// Lines starting with ``%`` or ``#`` are comments.
+pub(crate) fn read_words(filename: &str) -> Result<Vec<String>, Error> {
+    let content = read_file(filename)?;
+
+    let mut words = Vec::new();
+    for (line, text) in content.lines().enumerate() {
+        let text = text.map_err(|error| match error.kind() {
+            ErrorKind::InvalidData => Error::InvalidEncoding {
+                filename: filename.to_string(),
+                line: line + 1,
+            },
+            _ => Error::Io {
+                filename: filename.to_string(),
+                error,
+            },
+        })?;
+        if text.starts_with('%') || text.starts_with('#') {
+            continue;
+        }
+        words.extend(text.split_whitespace().map(String::from));
+    }
+
+    Ok(words)
+}
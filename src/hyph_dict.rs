@@ -13,107 +13,525 @@
 // FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
 // details.
 
-use std::cell::RefCell;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fs::OpenOptions;
-use std::io::{BufRead, BufReader};
-use std::rc::Rc;
+use std::fs;
+use std::io::BufRead;
+use std::mem;
+use std::path::Path;
+use std::sync::Arc;
 
 use regex::Captures;
 
-use super::{AlternativeParser, DataInt, PARSE, PARSE_HEX};
+use super::charset::Charset;
+use super::compiled::{self, Compiled, MAGIC};
+use super::exceptions::{ExceptionSource, Exceptions};
+use super::patterns::{Pattern, Patterns, Trie};
+use super::tex;
+use super::{
+    char_offsets, read_file, AlternativeParser, DataInt, Error, Metadata, PARSE, PARSE_HEX,
+};
+
+/// Compound level used for dictionaries without ``NEXTLEVEL``.
+const DEFAULT_COMPOUND: [&str; 4] = ["1-1", "1'1", "1\u{2013}1", "1\u{2019}1"];
+
+/// ``NOHYPHEN`` strings used for dictionaries without ``NEXTLEVEL``.
+const DEFAULT_NO_HYPHEN: [&str; 4] = ["'", "\u{2013}", "\u{2019}", "-"];
+
+/// A single level of hyphenation patterns.
+enum Level {
+    Parsed(Patterns),
+    Compiled(compiled::Patterns),
+}
+
+impl Level {
+    /// Get the character positions inside the lowercase word where the
+    /// patterns of this level allow a hyphen.
+    fn points(&self, word: &str) -> Vec<DataInt> {
+        let pointed_word = format!(".{}.", word);
+        let references = match self {
+            Level::Parsed(patterns) => patterns.references(&pointed_word),
+            Level::Compiled(trie) => trie.references(&pointed_word),
+        };
+        let length = references.len() - 1;
+
+        references
+            .into_iter()
+            .enumerate()
+            .filter(|(i, reference)| *i > 1 && *i < length - 1 && reference.value % 2 != 0)
+            .map(|(i, reference)| DataInt::with_ref(i - 1, &reference))
+            .collect()
+    }
+
+    /// Get a copy of this level, stored in memory, with the patterns added
+    /// or replaced.
+    fn merge(&self, patterns: HashMap<String, (usize, Vec<DataInt>)>) -> Self {
+        let mut merged: HashMap<_, _> = self.parsed().patterns().into_iter().collect();
+        merged.extend(patterns);
+
+        Level::Parsed(Patterns::new(merged))
+    }
+
+    /// Get the patterns of this level, stored in memory.
+    fn parsed(&self) -> Cow<'_, Patterns> {
+        match self {
+            Level::Parsed(patterns) => Cow::Borrowed(patterns),
+            Level::Compiled(trie) => Cow::Owned(Patterns::new(trie.patterns())),
+        }
+    }
+}
 
 /// Hyphenation patterns.
+///
+/// Dictionaries with a ``NEXTLEVEL`` line are split in two levels, as in
+/// libhyphen: the patterns of the first level find compound word boundaries,
+/// the patterns of the second level hyphenate the parts between them.
+///
+/// Dictionaries can be compiled to a binary format, see ``compile``, that is
+/// used without parsing the patterns again.
+///
+/// Dictionaries read from TeX files can also have exceptions, words
+/// hyphenated regardless of the patterns.
 pub struct HyphDict {
-    patterns: HashMap<String, (usize, Vec<DataInt>)>,
-    cache: RefCell<HashMap<String, Rc<Vec<DataInt>>>>,
-    maxlen: usize,
+    /// The header directives of the dictionary.
+    pub metadata: Metadata,
+    no_hyphen: Vec<String>,
+    compound: Level,
+    patterns: Level,
+    exceptions: Exceptions,
 }
 
 impl HyphDict {
     /// Read a ``hyph_*.dic`` and parse its patterns.
     ///
-    /// Returns `Err` if the filename does not exist
+    /// Compiled dictionaries, written by ``save``, are recognized and loaded
+    /// without parsing. Files ending with ``.tex`` or ``.pat.txt`` are read
+    /// as TeX patterns, see ``from_tex``.
+    ///
+    /// Returns `Err` if the file does not exist, cannot be read, or has a
+    /// malformed pattern.
     ///
     /// - *filename* - filename of hyph_*.dic to read
-    pub fn new(filename: &str) -> Result<Self, ()> {
-        let mut patterns = HashMap::new();
+    pub fn new(filename: &str) -> Result<Self, Error> {
+        if filename.ends_with(".tex") || filename.ends_with(".pat.txt") {
+            return Self::tex_file(filename);
+        }
+
+        Self::from_bytes(read_file(filename)?, filename)
+    }
+
+    /// Load a dictionary from its content.
+    ///
+    /// The content is parsed as a ``hyph_*.dic`` file, unless it is a
+    /// compiled dictionary written by ``compile``.
+    ///
+    /// Returns `Err` if the dictionary has a malformed pattern, or if it is
+    /// an invalid compiled dictionary.
+    ///
+    /// - *bytes* - content of the dictionary
+    /// - *filename* - name of the dictionary, used in errors
+    pub fn from_bytes(bytes: Vec<u8>, filename: &str) -> Result<Self, Error> {
+        if bytes.starts_with(MAGIC) {
+            Self::from_compiled(bytes, filename)
+        } else {
+            Self::read(&bytes[..], filename)
+        }
+    }
+
+    /// Load a compiled dictionary.
+    ///
+    /// The bytes are checked, but the patterns are read from them when words
+    /// are hyphenated: they can be a ``Vec<u8>``, a ``&'static [u8]``
+    /// included in the program, or a memory-mapped file.
+    ///
+    /// Returns `Err` if the bytes are not a compiled dictionary of the
+    /// current version.
+    ///
+    /// - *bytes* - compiled dictionary, as returned by ``compile``
+    /// - *filename* - name of the dictionary, used in errors
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::HyphDict;
+    ///
+    /// let hd = HyphDict::new("dictionaries/hyph_nl_NL.dic").unwrap();
+    /// let compiled = HyphDict::from_compiled(hd.compile(), "nl_NL").unwrap();
+    ///
+    /// assert_eq!(compiled.positions("lettergrepen"), hd.positions("lettergrepen"));
+    /// ```
+    pub fn from_compiled<B>(bytes: B, filename: &str) -> Result<Self, Error>
+    where
+        B: AsRef<[u8]> + Send + Sync + 'static,
+    {
+        let compiled = Compiled::new(Arc::new(bytes)).map_err(|reason| Error::InvalidCompiled {
+            filename: filename.to_string(),
+            reason,
+        })?;
+
+        Ok(Self {
+            metadata: compiled.metadata,
+            no_hyphen: compiled.no_hyphen,
+            compound: Level::Compiled(compiled.compound),
+            patterns: Level::Compiled(compiled.patterns),
+            exceptions: Exceptions::new(&[ExceptionSource::Words(compiled.exceptions)])?,
+        })
+    }
+
+    /// Compile the dictionary to a binary format.
+    ///
+    /// The patterns of both levels are stored as tries, see
+    /// ``from_compiled``.
+    pub fn compile(&self) -> Vec<u8> {
+        compiled::compile(
+            &self.metadata,
+            &self.no_hyphen,
+            &self.compound.parsed(),
+            &self.patterns.parsed(),
+            &self.exceptions.strings(),
+        )
+    }
+
+    /// Write the compiled dictionary to a file.
+    ///
+    /// Returns `Err` if the file cannot be written.
+    ///
+    /// - *filename* - filename of the compiled dictionary to write
+    pub fn save(&self, filename: &str) -> Result<(), Error> {
+        fs::write(filename, self.compile()).map_err(|error| Error::Io {
+            filename: filename.to_string(),
+            error,
+        })
+    }
+
+    /// Write the dictionary in the ``hyph_*.dic`` format of libhyphen.
+    ///
+    /// The header directives, the ``NEXTLEVEL`` compound level and the
+    /// nonstandard hyphenations are kept, so that ``read`` gives the same
+    /// dictionary back. libhyphen has no exceptions: they are written as
+    /// patterns of whole words, with the highest values, that give the same
+    /// positions unless other patterns have a value of 9 in these words.
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::HyphDict;
+    ///
+    /// let hd = HyphDict::new("dictionaries/hyph_hu_HU.dic").unwrap();
+    /// let dic = HyphDict::read(hd.to_dic().as_bytes(), "hu_HU").unwrap();
+    ///
+    /// assert_eq!(dic.compile(), hd.compile());
+    /// ```
+    pub fn to_dic(&self) -> String {
+        let mut dic = String::from("UTF-8\n");
+        let minima = [
+            ("LEFTHYPHENMIN", self.metadata.left_hyphen_min),
+            ("RIGHTHYPHENMIN", self.metadata.right_hyphen_min),
+            (
+                "COMPOUNDLEFTHYPHENMIN",
+                self.metadata.compound_left_hyphen_min,
+            ),
+            (
+                "COMPOUNDRIGHTHYPHENMIN",
+                self.metadata.compound_right_hyphen_min,
+            ),
+        ];
+        for (directive, minimum) in &minima {
+            if let Some(minimum) = minimum {
+                dic += &format!("{} {}\n", directive, minimum);
+            }
+        }
 
-        let stream = OpenOptions::new()
-            .read(true)
-            .open(filename)
-            .map_err(|_| ())?;
-        let stream = BufReader::new(stream);
+        // dictionaries without NEXTLEVEL get the default compound level and
+        // NOHYPHEN strings when they are read
+        let default_no_hyphen = match &self.metadata.no_hyphen {
+            no_hyphen if no_hyphen.is_empty() => {
+                DEFAULT_NO_HYPHEN.iter().map(|s| s.to_string()).collect()
+            }
+            no_hyphen => no_hyphen.clone(),
+        };
+        let default = self.no_hyphen == default_no_hyphen
+            && sorted(self.compound.parsed().patterns()) == sorted(default_compound());
+        let no_hyphen = match default {
+            true => &self.metadata.no_hyphen,
+            false => &self.no_hyphen,
+        };
+        if !no_hyphen.is_empty() {
+            dic += &format!("NOHYPHEN {}\n", no_hyphen.join(","));
+        }
 
-        for pattern in stream.lines() {
-            let pattern = pattern.unwrap();
-            if pattern.is_empty() || pattern.starts_with('%') || pattern.starts_with('#') {
-                continue;
+        if !default {
+            for pattern in sorted(self.compound.parsed().patterns()) {
+                dic += &format_pattern(&pattern, true);
+                dic.push('\n');
             }
+            dic += "NEXTLEVEL\n";
+        }
+        for pattern in sorted(self.patterns.parsed().patterns()) {
+            dic += &format_pattern(&pattern, true);
+            dic.push('\n');
+        }
 
-            // replace ^^hh with the real character
-            let mut pattern = PARSE_HEX
-                .replace_all(&pattern, |caps: &Captures| {
-                    let cap = &caps[1];
-                    let num = u8::from_str_radix(cap, 16).unwrap();
-                    let ch = num as char;
-
-                    ch.to_string()
-                })
-                .to_string();
-
-            // read nonstandard hyphen alternatives
-            let mut factory = if let Some(idx) = pattern.find('/') {
-                let alternative = pattern.split_off(idx + 1);
-                pattern.pop();
-                Some(AlternativeParser::new(&pattern, &alternative))
-            } else {
-                None
-            };
+        let exceptions = self.exceptions.strings();
+        if !exceptions.is_empty() {
+            dic += "% exceptions\n";
+        }
+        for exception in exceptions {
+            // the highest values, odd at the hyphens and even elsewhere
+            let mut pattern = String::from(".");
+            for (index, part) in exception.split('-').enumerate() {
+                for (offset, c) in part.chars().enumerate() {
+                    match (index, offset) {
+                        (0, 0) => (),
+                        (_, 0) => pattern.push('9'),
+                        _ => pattern.push('8'),
+                    }
+                    pattern.push(c);
+                }
+            }
+            dic += &pattern;
+            dic += ".\n";
+        }
 
-            let (tags, values): (Vec<_>, Vec<_>) = PARSE
-                .captures_iter(&pattern)
-                .map(|caps: Captures| {
-                    let i = caps
-                        .get(1)
-                        .map(|m| m.as_str())
-                        .filter(|m| !m.is_empty())
-                        .unwrap_or("0");
-                    let string = caps.get(2).map_or("", |m| m.as_str());
-
-                    let i: usize = i.parse().unwrap();
-                    let d = if let Some(factory) = &mut factory {
-                        factory.call(&DataInt::new(i, None))
-                    } else {
-                        DataInt::new(i, None)
-                    };
-
-                    (string, d)
-                })
-                .unzip();
-
-            // if only zeros, skip this pattern
-            if values.iter().map(|x| x.value).max().unwrap() == 0 {
-                continue;
+        dic
+    }
+
+    /// Write the dictionary as TeX hyphenation patterns, see ``from_tex``.
+    ///
+    /// The minima of the first and last syllables are written in the
+    /// ``hyphenmins`` header, the patterns in ``\patterns`` and the
+    /// exceptions in ``\hyphenation``. TeX has no compound level, no
+    /// ``NOHYPHEN`` strings and no nonstandard hyphenation: they are not
+    /// written, nor are the patterns with whitespace or TeX special
+    /// characters, that TeX cannot read.
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::HyphDict;
+    ///
+    /// let hd = HyphDict::new("dictionaries/hyph_nl_NL.dic").unwrap();
+    /// let tex = HyphDict::from_tex(hd.to_tex().as_bytes(), "hyph-nl.tex").unwrap();
+    ///
+    /// assert_eq!(tex.positions("lettergrepen"), hd.positions("lettergrepen"));
+    /// ```
+    pub fn to_tex(&self) -> String {
+        let mut tex = String::new();
+        let Metadata {
+            left_hyphen_min,
+            right_hyphen_min,
+            ..
+        } = self.metadata;
+        if left_hyphen_min.is_some() || right_hyphen_min.is_some() {
+            tex += "% hyphenmins:\n%     typesetting:\n";
+            if let Some(left) = left_hyphen_min {
+                tex += &format!("%         left: {}\n", left);
+            }
+            if let Some(right) = right_hyphen_min {
+                tex += &format!("%         right: {}\n", right);
             }
+        }
 
-            // chop zeros from beginning and end, and store start offset
-            let start = values.iter().position(|v| v.value != 0).unwrap_or(0);
-            let end = values
-                .iter()
-                .rposition(|v| v.value != 0)
-                .unwrap_or(values.len() - 1)
-                + 1;
+        tex += "\\patterns{\n";
+        let patterns = self
+            .patterns
+            .parsed()
+            .patterns()
+            .into_iter()
+            .filter(|(tags, _)| !tags.contains(|c: char| c.is_whitespace() || "%\\{}".contains(c)));
+        for pattern in sorted(patterns.collect()) {
+            tex += &format_pattern(&pattern, false);
+            tex.push('\n');
+        }
+        tex += "}\n";
 
-            patterns.insert(tags.concat(), (start, values[start..end].to_vec()));
+        let exceptions = self.exceptions.strings();
+        if !exceptions.is_empty() {
+            tex += "\\hyphenation{\n";
+            for exception in exceptions {
+                tex += &exception;
+                tex.push('\n');
+            }
+            tex += "}\n";
         }
 
-        let maxlen = patterns.keys().map(String::len).max().unwrap_or(0);
+        tex
+    }
 
-        Ok(Self {
+    /// Parse the patterns of a dictionary.
+    ///
+    /// Dictionaries declaring a character set on their first line, such as
+    /// ``ISO8859-2`` or ``KOI8-R`` in upstream ``hyph_*.dic`` files, are
+    /// decoded with it, the other ones are read as UTF-8.
+    ///
+    /// Returns `Err` if the dictionary cannot be read, has an unsupported
+    /// character set or has a malformed pattern.
+    ///
+    /// - *stream* - content of the dictionary
+    /// - *filename* - name of the dictionary, used in errors
+    pub fn read<R: BufRead>(stream: R, filename: &str) -> Result<Self, Error> {
+        Ok(Self::from_parsed(parse(stream, filename)?))
+    }
+
+    /// Parse TeX hyphenation patterns, as distributed by the hyph-utf8
+    /// project.
+    ///
+    /// The patterns are read from the ``\patterns`` commands, and the
+    /// exceptions from the ``\hyphenation`` commands. The minima of the
+    /// ``hyphenmins`` header, preferably the ``typesetting`` ones, are
+    /// stored as ``LEFTHYPHENMIN`` and ``RIGHTHYPHENMIN``. Content without
+    /// ``\patterns`` commands, such as ``.pat.txt`` files, is read as
+    /// patterns separated by whitespace.
+    ///
+    /// Returns `Err` if the patterns cannot be read or one of them is
+    /// malformed.
+    ///
+    /// - *stream* - content of the TeX file
+    /// - *filename* - name of the TeX file, used in errors
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::HyphDict;
+    ///
+    /// let tex = "% hyphenmins:\n\
+    ///            %     typesetting:\n\
+    ///            %         left: 1\n\
+    ///            %         right: 1\n\
+    ///            \\patterns{1ba}\n\
+    ///            \\hyphenation{bab-ab}\n";
+    /// let hd = HyphDict::from_tex(tex.as_bytes(), "hyph-xx.tex").unwrap();
+    ///
+    /// assert_eq!(hd.metadata.left_hyphen_min, Some(1));
+    /// assert_eq!(hd.positions("bababa").len(), 2);
+    /// assert_eq!(hd.positions("babab").len(), 1);
+    /// ```
+    pub fn from_tex<R: BufRead>(stream: R, filename: &str) -> Result<Self, Error> {
+        let tex = tex::read(stream, filename)?;
+        let mut patterns = HashMap::new();
+        for (line, pattern) in tex.patterns {
+            let parsed =
+                parse_pattern(&pattern, Charset::Utf8).map_err(|_| Error::MalformedPattern {
+                    filename: filename.to_string(),
+                    line,
+                    pattern: pattern.clone(),
+                })?;
+            if let Some((tags, values)) = parsed {
+                patterns.insert(tags, values);
+            }
+        }
+
+        let mut hd = Self::from_parsed(Parsed {
+            metadata: tex.metadata,
+            compound: None,
             patterns,
-            cache: RefCell::new(HashMap::new()),
-            maxlen,
+        });
+        for exception in &tex.exceptions {
+            hd.exceptions.insert(exception);
+        }
+
+        Ok(hd)
+    }
+
+    /// Read a TeX hyphenation file.
+    ///
+    /// The exceptions of ``.pat.txt`` files are read from the ``.hyp.txt``
+    /// file next to them, and their minima from the ``.tex`` file next to
+    /// them or in the ``tex`` directory next to theirs, as laid out in the
+    /// hyph-utf8 project.
+    fn tex_file(filename: &str) -> Result<Self, Error> {
+        let mut hd = Self::from_tex(&read_file(filename)?[..], filename)?;
+        let base = match filename.strip_suffix(".pat.txt") {
+            Some(base) => Path::new(base),
+            None => return Ok(hd),
+        };
+
+        let exceptions = base.with_extension("hyp.txt");
+        if exceptions.exists() {
+            hd.exceptions.read_file(&exceptions.to_string_lossy())?;
+        }
+        let name = base.file_name().unwrap_or_default();
+        let directory = base.parent().unwrap_or_else(|| Path::new(""));
+        let candidates = [
+            directory.join(name).with_extension("tex"),
+            directory.join("../tex").join(name).with_extension("tex"),
+        ];
+        if let Some(tex) = candidates.iter().find(|tex| tex.exists()) {
+            let tex = tex.to_string_lossy();
+            let metadata = tex::read(&read_file(&tex)?[..], &tex)?.metadata;
+            hd.metadata.update(metadata);
+        }
+
+        Ok(hd)
+    }
+
+    /// Build the levels of parsed patterns.
+    fn from_parsed(parsed: Parsed) -> Self {
+        let Parsed {
+            metadata,
+            compound,
+            patterns,
+        } = parsed;
+
+        let mut no_hyphen = metadata.no_hyphen.clone();
+        let compound = compound.unwrap_or_else(|| {
+            // like libhyphen, split words at hyphens and apostrophes when
+            // there is no compound level
+            if no_hyphen.is_empty() {
+                no_hyphen = DEFAULT_NO_HYPHEN.iter().map(|s| s.to_string()).collect();
+            }
+            default_compound().into_iter().collect()
+        });
+
+        Self {
+            metadata,
+            no_hyphen,
+            compound: Level::Parsed(Patterns::new(compound)),
+            patterns: Level::Parsed(Patterns::new(patterns)),
+            exceptions: Exceptions::default(),
+        }
+    }
+
+    /// Parse additional patterns and merge them on top of the dictionary.
+    ///
+    /// The additional patterns replace the patterns of the dictionary with
+    /// the same letters, the other patterns are combined as usual, the
+    /// highest value winning at each position. Patterns before a
+    /// ``NEXTLEVEL`` line are added to the compound level, the other ones to
+    /// the hyphenation level. The header directives found in the additional
+    /// patterns replace the directives of the dictionary.
+    ///
+    /// Returns `Err` if the patterns cannot be read or one of them is
+    /// malformed.
+    ///
+    /// - *stream* - content of the additional patterns
+    /// - *filename* - name of the additional patterns, used in errors
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::HyphDict;
+    ///
+    /// let hd = HyphDict::new("dictionaries/hyph_nl_NL.dic").unwrap();
+    /// let merged = hd.merge(&b"g1r\n"[..], "custom").unwrap();
+    ///
+    /// assert_eq!(merged.positions("lettergrepen").len(), 4);
+    /// ```
+    pub fn merge<R: BufRead>(&self, stream: R, filename: &str) -> Result<Self, Error> {
+        let Parsed {
+            metadata,
+            compound,
+            patterns,
+        } = parse(stream, filename)?;
+
+        let mut no_hyphen = self.no_hyphen.clone();
+        if !metadata.no_hyphen.is_empty() {
+            no_hyphen = metadata.no_hyphen.clone();
+        }
+        let mut merged = self.metadata.clone();
+        merged.update(metadata);
+
+        Ok(Self {
+            metadata: merged,
+            no_hyphen,
+            compound: self.compound.merge(compound.unwrap_or_default()),
+            patterns: self.patterns.merge(patterns),
+            exceptions: self.exceptions.clone(),
         })
     }
 
@@ -140,40 +558,331 @@ impl HyphDict {
     /// cut
     ///     how many characters to remove while substituting the nonstandard
     ///     hyphenation
-    pub fn positions(&self, word: &str) -> Rc<Vec<DataInt>> {
-        let word = word.to_lowercase();
-        if let Some(points) = self.cache.borrow().get(&word) {
-            return points.clone();
+    ///
+    /// The positions of the exceptions of the dictionary are used instead
+    /// of the patterns.
+    ///
+    /// The positions are computed for each call, ``Pyphen`` keeps them in a
+    /// cache.
+    pub fn positions(&self, word: &str) -> Vec<DataInt> {
+        if let Some(points) = self.exceptions.get(word) {
+            return points.to_vec();
         }
 
-        let pointed_word = format!(".{}.", word);
-        let mut references = vec![DataInt::new(0, None); pointed_word.len() + 1];
-
-        for i in 0..(pointed_word.len() - 1) {
-            for j in (i + 1)..(i + self.maxlen).min(pointed_word.len() + 1) {
-                let pattern = self.patterns.get(&pointed_word[i..j]);
-                if let Some(pattern) = pattern {
-                    let (offset, ref values) = *pattern;
-                    let (start, end) = (i + offset, i + offset + values.len());
-                    for (x, y) in references[start..end].iter_mut().zip(values.iter()) {
-                        if y.value > x.value {
-                            *x = y.clone();
-                        }
-                    }
+        self.hyphenate(&lowercase(word))
+    }
+
+    /// Get the character positions next to the ``NOHYPHEN`` strings of the
+    /// word, where it must not be hyphenated even if its patterns allow it.
+    pub fn no_hyphen_positions(&self, word: &str) -> Vec<usize> {
+        self.no_hyphen
+            .iter()
+            .flat_map(|string| {
+                word.match_indices(string.as_str())
+                    .flat_map(move |(index, _)| {
+                        let start = word[..index].chars().count();
+                        vec![start, start + string.chars().count()]
+                    })
+            })
+            .collect()
+    }
+
+    /// Get the positions where the lowercase word can be hyphenated.
+    ///
+    /// The word is split once at the compound boundaries found by the first
+    /// level and each part is hyphenated on its own by the second level, like
+    /// libhyphen does.
+    fn hyphenate(&self, word: &str) -> Vec<DataInt> {
+        let boundaries = self.compound.points(word);
+        if boundaries.is_empty() {
+            return self.patterns.points(word);
+        }
+
+        let offsets = char_offsets(word);
+        let mut points = Vec::new();
+        let mut begin = 0;
+        for boundary in boundaries {
+            let end = boundary.value;
+            points.extend(
+                self.patterns
+                    .points(&word[offsets[begin]..offsets[end]])
+                    .iter()
+                    .map(|point| DataInt::with_ref(begin + point.value, point)),
+            );
+            points.push(DataInt {
+                compound: true,
+                ..boundary
+            });
+            begin = end;
+        }
+        points.extend(
+            self.patterns
+                .points(&word[offsets[begin]..])
+                .iter()
+                .map(|point| DataInt::with_ref(begin + point.value, point)),
+        );
+
+        points
+    }
+}
+
+/// Patterns and header directives of a dictionary, before building its
+/// levels.
+struct Parsed {
+    metadata: Metadata,
+    /// Patterns before the ``NEXTLEVEL`` line, if any.
+    compound: Option<HashMap<String, (usize, Vec<DataInt>)>>,
+    patterns: HashMap<String, (usize, Vec<DataInt>)>,
+}
+
+/// Lines of a dictionary with their numbers, ``None`` if they cannot be
+/// decoded.
+pub(crate) type Lines = Vec<(usize, Option<String>)>;
+
+/// Read the lines of a dictionary, decoded with the character set declared
+/// by its first line.
+///
+/// Returns the character set and the lines after the declaration, with their
+/// numbers starting at 1, ``None`` for lines not valid in the character set.
+pub(crate) fn read_lines<R: BufRead>(stream: R, filename: &str) -> Result<(Charset, Lines), Error> {
+    let mut charset = Charset::Utf8;
+    let mut lines = Vec::new();
+
+    for (line, bytes) in stream.split(b'\n').enumerate() {
+        let mut bytes = bytes.map_err(|error| Error::Io {
+            filename: filename.to_string(),
+            error,
+        })?;
+        if bytes.ends_with(b"\r") {
+            bytes.pop();
+        }
+        if line == 0 && bytes.starts_with(b"\xef\xbb\xbf") {
+            bytes.drain(..3);
+        }
+        let text = charset.decode(&bytes);
+
+        // upstream dictionaries declare their character set first
+        if let (0, Some(text)) = (line, &text) {
+            match Charset::from_declaration(text) {
+                Some(Ok(declared)) => {
+                    charset = declared;
+                    continue;
                 }
+                Some(Err(())) => {
+                    return Err(Error::UnsupportedCharset {
+                        filename: filename.to_string(),
+                        charset: text.trim().to_string(),
+                    })
+                }
+                None => (),
             }
         }
 
-        let points: Vec<_> = references
-            .into_iter()
-            .enumerate()
-            .filter(|(_, reference)| reference.value % 2 != 0)
-            .map(|(i, reference)| DataInt::with_ref(i - 1, &reference))
+        lines.push((line + 1, text));
+    }
+
+    Ok((charset, lines))
+}
+
+/// Read the patterns and the header directives of a dictionary.
+fn parse<R: BufRead>(stream: R, filename: &str) -> Result<Parsed, Error> {
+    let mut patterns = HashMap::new();
+    let mut compound = None;
+    let mut metadata = Metadata::default();
+
+    let (charset, lines) = read_lines(stream, filename)?;
+    for (line, pattern) in lines {
+        let pattern = pattern.ok_or_else(|| Error::InvalidEncoding {
+            filename: filename.to_string(),
+            line,
+        })?;
+
+        if pattern.is_empty() || pattern.starts_with('%') || pattern.starts_with('#') {
+            continue;
+        }
+
+        // read header directives such as LEFTHYPHENMIN
+        let directive = metadata
+            .parse_line(&pattern)
+            .map_err(|_| Error::MalformedPattern {
+                filename: filename.to_string(),
+                line,
+                pattern: pattern.clone(),
+            })?;
+        if directive {
+            continue;
+        }
+
+        // patterns read so far are the compound level
+        if pattern.starts_with("NEXTLEVEL") {
+            compound = Some(mem::take(&mut patterns));
+            continue;
+        }
+
+        let parsed = parse_pattern(&pattern, charset).map_err(|_| Error::MalformedPattern {
+            filename: filename.to_string(),
+            line,
+            pattern: pattern.clone(),
+        })?;
+        if let Some((tags, values)) = parsed {
+            patterns.insert(tags, values);
+        }
+    }
+
+    Ok(Parsed {
+        metadata,
+        compound,
+        patterns,
+    })
+}
+
+/// Get the patterns of the compound level used for dictionaries without
+/// ``NEXTLEVEL``.
+fn default_compound() -> Vec<Pattern> {
+    DEFAULT_COMPOUND
+        .iter()
+        .filter_map(|pattern| parse_pattern(pattern, Charset::Utf8).unwrap())
+        .collect()
+}
+
+/// Sort the patterns by letters.
+fn sorted(mut patterns: Vec<Pattern>) -> Vec<Pattern> {
+    patterns.sort_by(|a, b| a.0.cmp(&b.0));
+    patterns
+}
+
+/// Write a pattern as in dictionaries, the inverse of ``parse_pattern``.
+///
+/// - *alternative* - whether to write the nonstandard hyphenation of the
+///   pattern, if any
+fn format_pattern(pattern: &Pattern, alternative: bool) -> String {
+    let (tags, (offset, values)) = pattern;
+    let tags: Vec<_> = tags.chars().collect();
+    let slots = (tags.len() + 1).max(offset + values.len());
+
+    let mut string = String::new();
+    let mut data = None;
+    for slot in 0..slots {
+        let value = slot
+            .checked_sub(*offset)
+            .and_then(|index| values.get(index));
+        let digit = value.map_or(0, |value| value.value);
+        // values after the last letter are consecutive digits, zeros
+        // included, as malformed patterns can have them
+        if digit != 0 || slot > tags.len() || (slot == tags.len() && slots > tags.len() + 1) {
+            string += &digit.to_string();
+        }
+        if let Some((change, index, cut)) = value.and_then(|value| value.data.as_ref()) {
+            // the parser stores the index relative to each value
+            data.get_or_insert((change, *index + slot as isize + 1, *cut));
+        }
+        if let Some(&c) = tags.get(slot) {
+            string.push(c);
+        }
+    }
+
+    match data {
+        Some((change, index, cut)) if alternative => {
+            let index = if tags.first() == Some(&'.') {
+                index - 1
+            } else {
+                index
+            };
+            format!("{}/{},{},{}", string, change, index, cut)
+        }
+        _ => string,
+    }
+}
+
+/// Lowercase the word, keeping the characters whose lowercase form is made of
+/// multiple characters so that positions in both words are the same.
+pub(crate) fn lowercase(word: &str) -> String {
+    let lower = word.to_lowercase();
+    if lower.chars().count() == word.chars().count() {
+        return lower;
+    }
+
+    word.chars()
+        .map(|c| {
+            let mut lower = c.to_lowercase();
+            match lower.len() {
+                1 => lower.next().unwrap(),
+                _ => c,
+            }
+        })
+        .collect()
+}
+
+/// Parse a pattern line into its letters, the offset of its first nonzero
+/// value and its values.
+///
+/// Returns ``None`` if the pattern only has zeros, and ``Err`` if it is
+/// malformed.
+///
+/// - *charset* - character set of the bytes written as ``^^hh``
+pub(crate) fn parse_pattern(pattern: &str, charset: Charset) -> Result<Option<Pattern>, ()> {
+    // replace runs of ^^hh with the real characters
+    let mut replaced = String::new();
+    let mut last = 0;
+    for run in PARSE_HEX.find_iter(pattern) {
+        // the regex only matches two hexadecimal digits after each ^^
+        let bytes: Vec<_> = run
+            .as_str()
+            .split("^^")
+            .skip(1)
+            .map(|hex| u8::from_str_radix(hex, 16).unwrap())
             .collect();
-        let points = Rc::new(points);
-        let points2 = Rc::clone(&points);
-        self.cache.borrow_mut().insert(word, points);
+        replaced.push_str(&pattern[last..run.start()]);
+        replaced.push_str(&charset.decode_hex(&bytes).ok_or(())?);
+        last = run.end();
+    }
+    replaced.push_str(&pattern[last..]);
+    let mut pattern = replaced;
+
+    // read nonstandard hyphen alternatives
+    let mut factory = if let Some(idx) = pattern.find('/') {
+        let alternative = pattern.split_off(idx + 1);
+        pattern.pop();
+        Some(AlternativeParser::new(&pattern, &alternative).ok_or(())?)
+    } else {
+        None
+    };
+
+    let (tags, values): (Vec<_>, Vec<_>) = PARSE
+        .captures_iter(&pattern)
+        .map(|caps: Captures| {
+            let i = caps
+                .get(1)
+                .map(|m| m.as_str())
+                .filter(|m| !m.is_empty())
+                .unwrap_or("0");
+            let string = caps.get(2).map_or("", |m| m.as_str());
+
+            let i: usize = i.parse().map_err(|_| ())?;
+            let d = if let Some(factory) = &mut factory {
+                factory.call(&DataInt::new(i, None))
+            } else {
+                DataInt::new(i, None)
+            };
+
+            Ok((string, d))
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .unzip();
 
-        points2
+    // if only zeros, skip this pattern
+    if values.iter().map(|x| x.value).max().unwrap() == 0 {
+        return Ok(None);
     }
+
+    // chop zeros from beginning and end, and store start offset
+    let start = values.iter().position(|v| v.value != 0).unwrap_or(0);
+    let end = values
+        .iter()
+        .rposition(|v| v.value != 0)
+        .unwrap_or(values.len() - 1)
+        + 1;
+
+    Ok(Some((tags.concat(), (start, values[start..end].to_vec()))))
 }
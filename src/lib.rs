@@ -21,19 +21,42 @@
 #![warn(missing_docs)]
 
 mod alternative_parser;
+mod cache;
+mod charset;
+mod compiled;
 mod data_int;
+mod error;
+mod exceptions;
 mod hyph_dict;
+mod lint;
+mod metadata;
+mod patgen;
+mod patterns;
 mod pyphen;
+mod tex;
 
-use std::cell::RefCell;
 use std::collections::HashMap;
-use std::rc::Rc;
-use std::thread_local;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
 
 use alternative_parser::AlternativeParser;
-use data_int::DataInt;
-use hyph_dict::HyphDict;
-pub use pyphen::{builder::Builder, iter::Iter, Pyphen};
+use cache::Cache;
+pub use cache::{CachePolicy, CacheStats};
+pub use data_int::DataInt;
+pub use error::Error;
+use exceptions::{ExceptionSource, Exceptions};
+pub use hyph_dict::HyphDict;
+pub use lint::{validate, Lint, LintKind};
+pub use metadata::Metadata;
+pub use patgen::{Patgen, PatgenLevel, PatgenReport, Training};
+pub use pyphen::{
+    builder::Builder,
+    evaluation::{Evaluation, WordEvaluation},
+    iter::Iter,
+    knuth_plass::{Breakpoint, KnuthPlass},
+    paragraph::Line,
+    text::words,
+    Pyphen,
+};
 
 #[macro_use]
 extern crate lazy_static;
@@ -42,39 +65,174 @@ use regex::Regex;
 
 // precompile some stuff
 lazy_static! {
-    static ref PARSE_HEX: Regex = Regex::new(r"\^{2}([0-9a-f]{2})").unwrap();
+    static ref PARSE_HEX: Regex = Regex::new(r"(\^{2}[0-9a-f]{2})+").unwrap();
     static ref PARSE: Regex = Regex::new(r"(\d?)(\D?)").unwrap();
 }
 
-thread_local! {
-    // cache of per-file HyphDict objects
-    static HD_CACHE: RefCell<HashMap<String, Rc<HyphDict>>> = RefCell::new(HashMap::new());
+lazy_static! {
+    // process-wide cache of per-dictionary HyphDict objects
+    static ref HD_CACHE: Mutex<HashMap<Source, Arc<HyphDict>>> = Mutex::new(HashMap::new());
 
-    /// A thread-local copy of all available languages
-    pub static LANGUAGES: RefCell<HashMap<String, Rc<String>>> = {
+    /// Dictionary files of the available languages, shared by all threads
+    ///
+    /// An entry is used instead of the dictionary embedded in the crate only
+    /// when it differs from the file of the ``dictionaries`` directory that
+    /// the embedded dictionary was built from.
+    pub static ref LANGUAGES: RwLock<HashMap<String, Arc<String>>> = {
         let mut dict = HashMap::new();
-        let dir = format!("{}/dictionaries", env!("CARGO_MANIFEST_DIR"));
+        let dir = DICTIONARIES.to_string();
 
         if let Ok(read_dir) = std::fs::read_dir(dir) {
-            for entry in read_dir {
-                if let Ok(entry) = entry {
-                    if let Some(filepath) = entry.path().to_str() {
-                        let filename = entry.file_name();
-                        let filename = filename
-                            .to_str()
-                            .unwrap()
-                            .trim_start_matches("hyph_")
-                            .trim_end_matches(".dic");
-                        dict.insert(filename.to_string(), Rc::new(filepath.to_string()));
-                    }
+            for entry in read_dir.flatten() {
+                if let Some(filepath) = entry.path().to_str() {
+                    let filename = entry.file_name();
+                    let filename = filename
+                        .to_str()
+                        .unwrap()
+                        .trim_start_matches("hyph_")
+                        .trim_end_matches(".dic");
+                    dict.insert(filename.to_string(), Arc::new(filepath.to_string()));
                 }
             }
         }
 
-        RefCell::new(dict)
+        RwLock::new(dict)
+    };
+}
+
+/// Directory of the dictionaries included in the crate.
+const DICTIONARIES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/dictionaries");
+
+/// Get the file of an included dictionary, as listed in ``LANGUAGES``.
+fn included_file(lang: &str) -> String {
+    format!("{}/hyph_{}.dic", DICTIONARIES, lang)
+}
+
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/embedded.rs"));
+}
+
+/// Where the patterns of a dictionary are read from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Source {
+    /// A dictionary compiled into the crate, by language.
+    Embedded(&'static str),
+    /// A dictionary file, by filename.
+    File(String),
+}
+
+impl Source {
+    /// Read and parse the dictionary.
+    fn load(&self) -> Result<HyphDict, Error> {
+        match self {
+            Source::Embedded(lang) => {
+                let (_, content) = embedded(lang).unwrap();
+                HyphDict::read(content.as_bytes(), &format!("hyph_{}.dic", lang))
+            }
+            Source::File(filename) => HyphDict::new(filename),
+        }
+    }
+
+    /// Get the dictionary from the process-wide cache, loading it if needed.
+    ///
+    /// - *cache* - whether to use a dictionary already in the cache
+    fn load_shared(self, cache: bool) -> Result<Arc<HyphDict>, Error> {
+        let cached = HD_CACHE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&self)
+            .cloned();
+        match cached {
+            Some(hd) if cache => Ok(hd),
+            _ => {
+                // read the file without holding the lock, other threads may
+                // need other dictionaries meanwhile
+                let hd = Arc::new(self.load()?);
+                let mut hc = HD_CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+                if cache {
+                    Ok(Arc::clone(hc.entry(self).or_insert(hd)))
+                } else {
+                    hc.insert(self, Arc::clone(&hd));
+                    Ok(hd)
+                }
+            }
+        }
+    }
+}
+
+/// Additional patterns merged on top of a dictionary.
+#[derive(Clone, Debug)]
+enum PatternSource {
+    /// Patterns given as a string.
+    Text(String),
+    /// A file of patterns, by filename.
+    File(String),
+}
+
+impl PatternSource {
+    /// Read the patterns and merge them on top of the dictionary.
+    fn merge(&self, hd: &HyphDict) -> Result<HyphDict, Error> {
+        match self {
+            PatternSource::Text(patterns) => hd.merge(patterns.as_bytes(), "<patterns>"),
+            PatternSource::File(filename) => hd.merge(&read_file(filename)?[..], filename),
+        }
     }
 }
 
+/// Read the content of a file.
+///
+/// Returns `Err` if the file does not exist or cannot be read.
+fn read_file(filename: &str) -> Result<Vec<u8>, Error> {
+    std::fs::read(filename).map_err(|error| match error.kind() {
+        std::io::ErrorKind::NotFound => Error::MissingFile {
+            filename: filename.to_string(),
+        },
+        _ => Error::Io {
+            filename: filename.to_string(),
+            error,
+        },
+    })
+}
+
+/// Get the language and the content of the dictionary embedded for the
+/// language, if any.
+fn embedded(lang: &str) -> Option<(&'static str, &'static str)> {
+    embedded::EMBEDDED
+        .binary_search_by_key(&lang, |&(lang, _)| lang)
+        .ok()
+        .map(|index| embedded::EMBEDDED[index])
+}
+
+/// Get the byte offsets of the characters of the word, followed by its length.
+fn char_offsets(word: &str) -> Vec<usize> {
+    word.char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(word.len()))
+        .collect()
+}
+
+/// Get all the available languages, sorted.
+///
+/// These are the languages embedded in the crate, enabled by the ``lang-*``
+/// cargo features, and the languages of ``LANGUAGES``.
+///
+/// # Example
+/// ```
+/// assert!(pyphen_rs::languages().contains(&"nl_NL".to_string()));
+/// ```
+pub fn languages() -> Vec<String> {
+    let files = LANGUAGES.read().unwrap_or_else(PoisonError::into_inner);
+    let mut languages: Vec<_> = embedded::EMBEDDED
+        .iter()
+        .map(|(lang, _)| lang.to_string())
+        .chain(files.keys().cloned())
+        .collect();
+    languages.sort();
+    languages.dedup();
+
+    languages
+}
+
 /// Get a fallback language if one is available in our dictionaries.
 ///
 /// <http://www.unicode.org/reports/tr35/#Locale_Inheritance>
@@ -82,25 +240,21 @@ thread_local! {
 /// We use the normal truncation inheritance. This function needs aliases
 /// including scripts for languages with multiple regions available.
 pub fn language_fallback(language: &str) -> Option<String> {
-    let language = language.replace('-', "_");
-    let mut parts: Vec<_> = language.split('_').collect();
-
-    while !parts.is_empty() {
-        let language = parts.join("_");
-        let mut flag = false;
-        LANGUAGES.with(|l| {
-            if l.borrow().contains_key(&language) {
-                flag = true;
-            }
-        });
-        if flag {
-            return Some(language);
-        }
+    let languages = LANGUAGES.read().unwrap_or_else(PoisonError::into_inner);
+    fallback_chain(language)
+        .into_iter()
+        .find(|language| embedded(language).is_some() || languages.contains_key(language))
+}
 
-        parts.pop();
-    }
+/// Get the languages tried by ``language_fallback``, in order.
+fn fallback_chain(language: &str) -> Vec<String> {
+    let language = language.replace('-', "_");
+    let parts: Vec<_> = language.split('_').collect();
 
-    None
+    (1..=parts.len())
+        .rev()
+        .map(|length| parts[..length].join("_"))
+        .collect()
 }
 
 #[cfg(test)]
@@ -174,27 +328,75 @@ mod tests {
     /// Test a missing dict.
     #[test]
     fn test_missing_dict() {
-        assert!(Builder::lang("mi_SS").build().is_err());
+        match Builder::lang("mi_SS").build() {
+            Err(Error::UnknownLanguage { language, tried }) => {
+                assert_eq!(language, "mi_SS");
+                assert_eq!(tried, vec!["mi_SS", "mi"]);
+            }
+            _ => panic!("mi_SS should be unknown"),
+        }
+    }
+
+    /// Test a missing dictionary file.
+    #[test]
+    fn test_missing_file() {
+        let filename = Arc::new("dictionaries/hyph_mi_SS.dic".to_string());
+        match Builder::filename(filename).build() {
+            Err(Error::MissingFile { filename }) => {
+                assert_eq!(filename, "dictionaries/hyph_mi_SS.dic");
+            }
+            _ => panic!("hyph_mi_SS.dic should be missing"),
+        }
+    }
+
+    /// Test dictionaries with malformed patterns.
+    #[test]
+    fn test_malformed_pattern() {
+        let filename = std::env::temp_dir().join("pyphen_rs_malformed.dic");
+        let filename = Arc::new(filename.to_str().unwrap().to_string());
+        for (content, line) in &[
+            ("LEFTHYPHENMIN 2\na1b\nc1d/c=,1\n", 3),
+            ("a1b\nc1d/cd,1,1\n", 2),
+            ("a1b\n%\nx\u{663}y\n", 3),
+            ("a1b\n1b/x=y,7,3\n", 2),
+            ("a1b\n1b/x=y,0,1\n", 2),
+            ("LEFTHYPHENMIN abc\na1b\n", 1),
+            ("a1b\nRIGHTHYPHENMIN\n", 2),
+        ] {
+            std::fs::write(&*filename, content).unwrap();
+            match Builder::filename(Arc::clone(&filename)).build() {
+                Err(Error::MalformedPattern { line: l, .. }) => assert_eq!(l, *line),
+                _ => panic!("{:?} should be malformed", content),
+            }
+        }
+
+        std::fs::write(&*filename, b"a1b\n\xff1c\n").unwrap();
+        match Builder::filename(Arc::clone(&filename)).build() {
+            Err(Error::InvalidEncoding { line, .. }) => assert_eq!(line, 2),
+            _ => panic!("invalid UTF-8 should be detected"),
+        }
+        std::fs::remove_file(&*filename).unwrap();
     }
 
     /// Test a personal dict.
     #[test]
     fn test_personal_dict() {
-        let dic = Builder::lang("fr").build().unwrap();
-        assert_ne!(
-            dic.inserted("autobandventieldopje"),
-            "au-to-band-ven-tiel-dop-je"
-        );
-        LANGUAGES.with(|l| {
-            let nl = {
-                let l = l.borrow();
-                l["nl_NL"].clone()
-            };
-            let mut l = l.borrow_mut();
-            let fr = l.get_mut("fr").unwrap();
-            *fr = nl;
-        });
-        let dic = Builder::lang("fr").build().unwrap();
+        struct Personal;
+
+        impl Drop for Personal {
+            fn drop(&mut self) {
+                LANGUAGES.write().unwrap().remove("xx_personal");
+            }
+        }
+
+        assert_eq!(language_fallback("xx_personal"), None);
+        let _personal = Personal;
+        {
+            let mut l = LANGUAGES.write().unwrap();
+            let nl = l["nl_NL"].clone();
+            l.insert("xx_personal".to_string(), nl);
+        }
+        let dic = Builder::lang("xx_personal").build().unwrap();
         assert_eq!(
             dic.inserted("autobandventieldopje"),
             "au-to-band-ven-tiel-dop-je"
@@ -214,16 +416,69 @@ mod tests {
         assert_eq!(dic.inserted("lettergrepen"), "letter-grepen");
     }
 
+    /// Test the ``LEFTHYPHENMIN`` and ``RIGHTHYPHENMIN`` directives.
+    #[test]
+    fn test_hyphenmin_directives() {
+        let dic = Builder::lang("en_US").build().unwrap();
+        assert_eq!(dic.metadata().left_hyphen_min, Some(2));
+        assert_eq!(dic.metadata().right_hyphen_min, Some(3));
+        assert_eq!((dic.left(), dic.right()), (2, 3));
+
+        let dic = Builder::lang("en_US").right(1).build().unwrap();
+        assert_eq!(dic.metadata().right_hyphen_min, Some(3));
+        assert_eq!((dic.left(), dic.right()), (2, 1));
+
+        let dic = Builder::lang("nl_NL").build().unwrap();
+        assert_eq!(dic.metadata(), &Metadata::default());
+        assert_eq!((dic.left(), dic.right()), (2, 2));
+    }
+
+    /// Test the compound level of two-level dictionaries.
+    #[test]
+    fn test_compound() {
+        let dic = Builder::lang("de_DE").build().unwrap();
+        assert_eq!(dic.inserted("Druckerzeugnis"), "Druck-er-zeug-nis");
+        assert_eq!(dic.compound_positions("Druckerzeugnis"), vec![5]);
+        assert_eq!((dic.compound_left(), dic.compound_right()), (2, 2));
+
+        let dic = Builder::lang("de_DE").compound_left(4).build().unwrap();
+        assert_eq!(dic.inserted("Kindergarten"), "Kin-der-garten");
+
+        // parts are only hyphenated by the second level
+        let dic = Builder::from_str("UTF-8\n.a1b\n.b1a\nNEXTLEVEL\n1c\n")
+            .left(1)
+            .right(1)
+            .compound_left(1)
+            .compound_right(1)
+            .build()
+            .unwrap();
+        assert_eq!(dic.inserted("abab"), "a-bab");
+    }
+
+    /// Test the ``NOHYPHEN`` directive and words with explicit hyphens.
+    #[test]
+    fn test_no_hyphen() {
+        let dic = Builder::lang("de_DE").build().unwrap();
+        assert_eq!(dic.metadata().no_hyphen, vec!["-", "'"]);
+        assert_eq!(
+            dic.inserted("Mutter-Kind-Beziehung"),
+            "Mut-ter-Kind-Be-zie-hung"
+        );
+
+        let dic = Builder::lang("en_US").build().unwrap();
+        assert!(dic.metadata().no_hyphen.is_empty());
+        assert_eq!(dic.inserted("self-determination"), "self-de-ter-mi-na-tion");
+        assert_eq!(dic.inserted("rock'n'roll"), "rock'n'roll");
+        assert_eq!(dic.iterate("well-known").next(), None);
+    }
+
     /// Test the ``filename`` parameter.
     #[test]
     fn test_filename() {
-        LANGUAGES.with(|l| {
-            let l = l.borrow();
-            let filename = l["nl_NL"].clone();
+        let filename = LANGUAGES.read().unwrap()["nl_NL"].clone();
 
-            let dic = Builder::filename(filename).build().unwrap();
-            assert_eq!(dic.inserted("lettergrepen"), "let-ter-gre-pen");
-        });
+        let dic = Builder::filename(filename).build().unwrap();
+        assert_eq!(dic.inserted("lettergrepen"), "let-ter-gre-pen");
     }
 
     /// Test the alternative Parser.
@@ -235,6 +490,10 @@ mod tests {
         match_iter(iter.next(), "ku", "lissza");
         assert_eq!(iter.next(), None);
         assert_eq!(dic.inserted("kulissza"), "ku-lisz-sza");
+
+        // the change has to replace letters of the pattern
+        assert!(Builder::from_str("1b/x=y,7,3\n").build().is_err());
+        assert!(Builder::from_str("1b/x=y,-1,1\n").build().is_err());
     }
 
     /// Test uppercase.
@@ -258,11 +517,378 @@ mod tests {
     /// Test that all included dictionaries can be parsed.
     #[test]
     fn test_all_dictionaries() {
-        LANGUAGES.with(|l| {
-            for lang in l.borrow().keys() {
-                Builder::lang(lang).build().unwrap();
+        let languages: Vec<_> = LANGUAGES.read().unwrap().keys().cloned().collect();
+        for lang in languages {
+            Builder::lang(&lang).build().unwrap();
+        }
+    }
+
+    /// Test that instances can be shared between threads.
+    #[test]
+    fn test_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Pyphen>();
+        assert_send_sync::<Error>();
+
+        let dic = Arc::new(Builder::lang("nl_NL").build().unwrap());
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let dic = Arc::clone(&dic);
+                std::thread::spawn(move || {
+                    let other = Builder::lang("nl_NL").build().unwrap();
+                    assert!(std::ptr::eq(dic.metadata(), other.metadata()));
+                    for _ in 0..100 {
+                        assert_eq!(dic.inserted("lettergrepen"), "let-ter-gre-pen");
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    /// Test the dictionaries embedded in the crate.
+    #[test]
+    fn test_embedded() {
+        assert_eq!(embedded("nl_NL").unwrap().0, "nl_NL");
+        assert!(embedded("mi_SS").is_none());
+        assert!(languages().windows(2).all(|pair| pair[0] < pair[1]));
+        for (lang, content) in embedded::EMBEDDED {
+            assert!(!content.is_empty(), "{}", lang);
+            assert!(languages().contains(&lang.to_string()));
+        }
+
+        let dic = Builder::lang("nl-NL-variant").build().unwrap();
+        assert_eq!(dic.inserted("lettergrepen"), "let-ter-gre-pen");
+    }
+
+    /// Test compiled dictionaries.
+    #[test]
+    fn test_compiled() {
+        let filename = std::env::temp_dir().join("pyphen_rs_compiled.bin");
+        let filename = Arc::new(filename.to_str().unwrap().to_string());
+        HyphDict::new("dictionaries/hyph_hu_HU.dic")
+            .unwrap()
+            .save(&filename)
+            .unwrap();
+        let dic = Builder::filename(Arc::clone(&filename))
+            .left(1)
+            .right(1)
+            .build()
+            .unwrap();
+        assert_eq!(dic.inserted("kulissza"), "ku-lisz-sza");
+        std::fs::remove_file(&*filename).unwrap();
+
+        let hd = HyphDict::new("dictionaries/hyph_de_DE.dic").unwrap();
+        let compiled = hd.compile();
+        let hd = HyphDict::from_compiled(compiled.clone(), "de_DE").unwrap();
+        assert_eq!(hd.compile(), compiled);
+        let dic = Builder::dictionary(Arc::new(hd)).build().unwrap();
+        assert_eq!(dic.inserted("Druckerzeugnis"), "Druck-er-zeug-nis");
+        assert_eq!(dic.metadata().no_hyphen, vec!["-", "'"]);
+
+        let mut invalid = compiled.clone();
+        invalid[8] = 3;
+        match HyphDict::from_compiled(invalid, "de_DE") {
+            Err(Error::InvalidCompiled { reason, .. }) => {
+                assert_eq!(reason, "unsupported version 3")
+            }
+            _ => panic!("version 3 should be unsupported"),
+        }
+        for length in &[0, 20, 100, compiled.len() - 1] {
+            match HyphDict::from_compiled(compiled[..*length].to_vec(), "de_DE") {
+                Err(Error::InvalidCompiled { .. }) => (),
+                _ => panic!("truncated data should be invalid"),
+            }
+        }
+    }
+
+    /// Test the policies of the cache of positions.
+    #[test]
+    fn test_cache() {
+        let dic = Builder::lang("nl_NL")
+            .cache_policy(CachePolicy::MaxEntries(2))
+            .build()
+            .unwrap();
+        dic.positions("lettergrepen");
+        dic.positions("woordafbreking");
+        dic.positions("lettergrepen");
+        dic.positions("lettergreep");
+        dic.positions("woordafbreking");
+        let stats = dic.cache_stats();
+        assert_eq!((stats.hits, stats.misses), (1, 4));
+        assert_eq!((stats.evictions, stats.entries), (2, 2));
+
+        let clone = dic.clone();
+        clone.clear_cache();
+        let stats = dic.cache_stats();
+        assert_eq!(
+            (stats.hits, stats.misses, stats.entries, stats.bytes),
+            (1, 4, 0, 0)
+        );
+
+        let dic = Builder::lang("nl_NL")
+            .cache_policy(CachePolicy::Disabled)
+            .build()
+            .unwrap();
+        assert_eq!(dic.inserted("lettergrepen"), "let-ter-gre-pen");
+        assert_eq!(dic.inserted("lettergrepen"), "let-ter-gre-pen");
+        let stats = dic.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (0, 2, 0));
+
+        let dic = Builder::lang("nl_NL")
+            .cache_policy(CachePolicy::MaxBytes(300))
+            .build()
+            .unwrap();
+        for word in "een lange zin met heel veel verschillende woorden".split(' ') {
+            dic.positions(word);
+        }
+        let stats = dic.cache_stats();
+        assert!(stats.bytes <= 300 && stats.entries > 0 && stats.evictions > 0);
+
+        let dic = Builder::lang("nl_NL").build().unwrap();
+        for _ in 0..3 {
+            dic.positions("lettergrepen");
+        }
+        let stats = dic.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (2, 1, 0));
+    }
+
+    /// Test dictionaries encoded with legacy character sets.
+    #[test]
+    fn test_charsets() {
+        for (lang, declaration, words) in &[
+            ("ru_RU", "KOI8-R", &["перенос", "словарь", "Ёлка"]),
+            ("ru_RU", "microsoft-cp1251", &["перенос", "словарь", "Ёлка"]),
+            ("hu_HU", "ISO8859-2", &["kulissza", "őrültség", "Összetett"]),
+            (
+                "da_DK",
+                "ISO8859-1",
+                &["orddeling", "bøgetræerne", "Ærøskøbing"],
+            ),
+        ] {
+            let charset = charset::Charset::from_declaration(declaration)
+                .unwrap()
+                .unwrap();
+            let bytes: HashMap<_, _> = (0..=255)
+                .filter_map(|byte| Some((charset.decode(&[byte])?, byte)))
+                .collect();
+            let encode = |c: char| bytes.get(&c.to_string()).copied();
+            let content = std::fs::read_to_string(format!("dictionaries/hyph_{}.dic", lang));
+            let mut encoded = format!("{}\n", declaration).into_bytes();
+            for line in content.unwrap().lines() {
+                // skip the comments that cannot be encoded
+                if let Some(bytes) = line.chars().map(encode).collect::<Option<Vec<_>>>() {
+                    encoded.extend(bytes);
+                    encoded.push(b'\n');
+                }
+            }
+
+            let dic = Builder::lang(lang).build().unwrap();
+            let legacy = Builder::from_bytes(encoded).build().unwrap();
+            for word in words.iter() {
+                assert_eq!(legacy.inserted(word), dic.inserted(word), "{}", declaration);
+            }
+        }
+
+        for (content, word, inserted) in &[
+            ("ISO8859-2\n1^^b3\n", "ałb", "a-łb"),
+            ("KOI8-R\n1^^c1\n", "бабаб", "б-аб-аб"),
+            ("UTF-8\n1^^c5^^82\n", "ałb", "a-łb"),
+            ("1^^e9\n", "aébé", "a-éb-é"),
+        ] {
+            let dic = Builder::from_str(content).left(1).right(1).build().unwrap();
+            assert_eq!(dic.inserted(word), *inserted);
+        }
+
+        assert!(Builder::from_bytes(&b"KOI8-R\na1b\n\xff1c\n"[..])
+            .build()
+            .is_ok());
+        match Builder::from_bytes(&b"ISO8859-3\na1b\n\xa51c\n"[..]).build() {
+            Err(Error::InvalidEncoding { line, .. }) => assert_eq!(line, 3),
+            _ => panic!("undefined bytes should be detected"),
+        }
+        match Builder::from_str("ISCII-DEVANAGARI\na1b\n").build() {
+            Err(Error::UnsupportedCharset { charset, .. }) => {
+                assert_eq!(charset, "ISCII-DEVANAGARI")
+            }
+            _ => panic!("ISCII should be unsupported"),
+        }
+    }
+
+    /// Test TeX hyphenation files.
+    #[test]
+    fn test_tex() {
+        let directory = std::env::temp_dir().join("pyphen_rs_tex");
+        std::fs::create_dir_all(directory.join("tex")).unwrap();
+        std::fs::create_dir_all(directory.join("txt")).unwrap();
+        let tex = directory.join("tex/hyph-xx.tex");
+        std::fs::write(
+            &tex,
+            "% title: Test patterns\n\
+             % hyphenmins:\n\
+             %     generation:\n\
+             %         left: 2\n\
+             %         right: 2\n\
+             %     typesetting:\n\
+             %         left: 1\n\
+             %         right: 3\n\
+             % texlive:\n\
+             %     left: 5\n\
+             \\message{Test patterns \\% not a comment}\n\
+             \\lccode`\\'=`\\'\n\
+             \\patterns{ % patterns\n\
+             1ba\n\
+             .ab1\n\
+             }\n\
+             \\hyphenation{\n\
+             ba-bab-ab\n\
+             }\n",
+        )
+        .unwrap();
+        let txt = directory.join("txt/hyph-xx.pat.txt");
+        std::fs::write(&txt, "1ba\n.ab1\n").unwrap();
+        std::fs::write(directory.join("txt/hyph-xx.hyp.txt"), "ba-bab-ab\n").unwrap();
+
+        for filename in &[tex, txt] {
+            let hd = HyphDict::new(filename.to_str().unwrap()).unwrap();
+            assert_eq!(hd.metadata.left_hyphen_min, Some(1));
+            assert_eq!(hd.metadata.right_hyphen_min, Some(3));
+            let dic = Builder::dictionary(Arc::new(hd)).build().unwrap();
+            assert_eq!(dic.inserted("abababa"), "a-b-a-baba");
+            assert_eq!(dic.inserted("Bababab"), "Ba-babab");
+
+            // exceptions are kept by compiled dictionaries
+            let hd = HyphDict::new(filename.to_str().unwrap()).unwrap();
+            let compiled = HyphDict::from_compiled(hd.compile(), "xx").unwrap();
+            let dic = Builder::dictionary(Arc::new(compiled)).build().unwrap();
+            assert_eq!(dic.inserted("Bababab"), "Ba-babab");
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        match HyphDict::from_tex(&b"\\patterns{\n1a\n\\'e1\n}"[..], "hyph-xx.tex") {
+            Err(Error::MalformedPattern { line, .. }) => assert_eq!(line, 3),
+            _ => panic!("TeX commands in patterns should be malformed"),
+        }
+    }
+
+    /// Test dictionaries given as strings, bytes or readers.
+    #[test]
+    fn test_memory() {
+        let content = std::fs::read_to_string("dictionaries/hyph_nl_NL.dic").unwrap();
+        let dic = Builder::from_str(&content).build().unwrap();
+        assert_eq!(dic.inserted("lettergrepen"), "let-ter-gre-pen");
+
+        let file = std::fs::File::open("dictionaries/hyph_hu_HU.dic").unwrap();
+        let dic = Builder::from_reader(file).left(1).right(1).build().unwrap();
+        assert_eq!(dic.inserted("kulissza"), "ku-lisz-sza");
+
+        let hd = HyphDict::new("dictionaries/hyph_de_DE.dic").unwrap();
+        let dic = Builder::from_bytes(hd.compile()).build().unwrap();
+        assert_eq!(dic.inserted("Druckerzeugnis"), "Druck-er-zeug-nis");
+        let dic = Builder::from_bytes(content.as_bytes()).build().unwrap();
+        assert_eq!(dic.inserted("lettergrepen"), "let-ter-gre-pen");
+
+        match Builder::from_bytes(&b"1a\n\xff1b\n"[..]).build() {
+            Err(Error::InvalidEncoding { filename, line }) => {
+                assert_eq!((filename.as_str(), line), ("<memory>", 2))
+            }
+            _ => panic!("invalid UTF-8 should be an error"),
+        }
+
+        struct Failing;
+        impl std::io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("broken"))
+            }
+        }
+        match Builder::from_reader(Failing).build() {
+            Err(Error::Io { error, .. }) => assert_eq!(error.to_string(), "broken"),
+            _ => panic!("reading errors should be returned"),
+        }
+    }
+
+    /// Test the patterns merged on top of a dictionary.
+    #[test]
+    fn test_patterns() {
+        let filename = std::env::temp_dir().join("pyphen_rs_patterns.dic");
+        let filename = filename.to_str().unwrap().to_string();
+        std::fs::write(&filename, "LEFTHYPHENMIN 4\ng2r\n").unwrap();
+
+        let dic = Builder::lang("nl_NL").patterns("g1r").build().unwrap();
+        assert_eq!(dic.inserted("lettergrepen"), "let-ter-g-re-pen");
+        let dic = Builder::lang("nl_NL")
+            .patterns("g1r")
+            .patterns_file(&filename)
+            .build()
+            .unwrap();
+        assert_eq!(dic.inserted("lettergrepen"), "letter-gre-pen");
+        assert_eq!(dic.left(), 4);
+        std::fs::remove_file(&filename).unwrap();
+
+        // the shared dictionary is left untouched
+        let dic = Builder::lang("nl_NL").build().unwrap();
+        assert_eq!(dic.inserted("lettergrepen"), "let-ter-gre-pen");
+
+        let hd = HyphDict::new("dictionaries/hyph_de_DE.dic").unwrap();
+        let hd = HyphDict::from_compiled(hd.compile(), "de_DE").unwrap();
+        let dic = Builder::dictionary(Arc::new(hd))
+            .patterns("NEXTLEVEL\nz1e")
+            .build()
+            .unwrap();
+        assert_eq!(dic.inserted("Druckerzeugnis"), "Druck-er-z-eug-nis");
+
+        // patterns before NEXTLEVEL find compound word boundaries
+        let dic = Builder::lang("nl_NL")
+            .patterns("r1g\nNEXTLEVEL")
+            .build()
+            .unwrap();
+        assert_eq!(dic.compound_positions("lettergrepen"), vec![6]);
+
+        match Builder::lang("nl_NL").patterns("a/b").build() {
+            Err(Error::MalformedPattern { filename, line, .. }) => {
+                assert_eq!((filename.as_str(), line), ("<patterns>", 1))
             }
-        });
+            _ => panic!("malformed pattern should be an error"),
+        }
+    }
+
+    /// Test the exceptions given to the builder.
+    #[test]
+    fn test_exceptions() {
+        let filename = std::env::temp_dir().join("pyphen_rs_exceptions.txt");
+        let filename = filename.to_str().unwrap().to_string();
+        std::fs::write(&filename, "% comment\nta-ble   ex-cep-tion\nlettergrepen\n").unwrap();
+
+        let dic = Builder::lang("nl_NL")
+            .exceptions(vec!["ex-ception", "ta-bl-e"])
+            .exceptions_file(&filename)
+            .exceptions(vec!["Sy-nop-sis"])
+            .build()
+            .unwrap();
+        assert_eq!(dic.inserted("table"), "ta-ble");
+        assert_eq!(dic.inserted("exception"), "ex-cep-tion");
+        assert_eq!(dic.inserted("lettergrepen"), "lettergrepen");
+        assert_eq!(dic.inserted("SYNOPSIS"), "SY-NOP-SIS");
+        assert_eq!(dic.inserted("Tabel"), "Ta-bel");
+        match_iter(dic.iterate("Table").next(), "Ta", "ble");
+
+        // exceptions are still limited by the syllable lengths
+        let dic = Builder::lang("nl_NL")
+            .exceptions(vec!["a-b-cd-e-f"])
+            .build()
+            .unwrap();
+        assert_eq!(dic.inserted("abcdef"), "ab-cd-ef");
+        std::fs::remove_file(&filename).unwrap();
+
+        match Builder::lang("nl_NL")
+            .exceptions_file("non_existent.txt")
+            .build()
+        {
+            Err(Error::MissingFile { filename }) => assert_eq!(filename, "non_existent.txt"),
+            _ => panic!("missing file should be an error"),
+        }
     }
 
     /// Test the language fallback algorithm.
@@ -276,4 +902,564 @@ mod tests {
         test_lang(language_fallback("fr-Latn-FR"), "fr");
         test_lang(language_fallback("en-US_variant1-x"), "en_US");
     }
+
+    /// Test patterns generated from hyphenated words.
+    #[test]
+    fn test_patgen() {
+        let en = Builder::lang("en_US").build().unwrap();
+        let plain = [
+            "hyphenation",
+            "dictionary",
+            "patterns",
+            "generation",
+            "training",
+            "computer",
+            "algorithm",
+            "paragraph",
+            "justification",
+            "typesetting",
+            "letters",
+            "syllable",
+            "character",
+            "language",
+            "project",
+            "independent",
+            "information",
+            "development",
+            "beautiful",
+            "important",
+        ];
+        let words: Vec<_> = plain.iter().map(|word| en.inserted(word)).collect();
+
+        let level = |lengths, threshold| PatgenLevel {
+            lengths,
+            good_weight: 1,
+            bad_weight: 1,
+            threshold,
+        };
+        let training = Patgen::new(&words)
+            .level(level(1..=3, 2))
+            .level(level(2..=4, 1))
+            .level(level(3..=5, 1))
+            .level(level(4..=6, 1))
+            .train();
+        assert_eq!(training.reports.len(), 4);
+        let reports: Vec<_> = training
+            .reports
+            .iter()
+            .map(|report| (report.patterns, report.good, report.bad, report.missed))
+            .collect();
+        assert_eq!(reports[0], (6, 24, 8, 14));
+        assert_eq!(reports[1], (6, 24, 0, 14));
+        assert_eq!(reports[3], (0, 38, 0, 0));
+
+        // reports give the hyphens found by the generated dictionary
+        let dic = Builder::dictionary(Arc::new(training.hyph_dict().unwrap()))
+            .build()
+            .unwrap();
+        let (mut good, mut bad, mut missed) = (0, 0, 0);
+        for word in &plain {
+            let expected = en.positions(word);
+            let found = dic.positions(word);
+            good += found.iter().filter(|&i| expected.contains(i)).count();
+            bad += found.iter().filter(|&i| !expected.contains(i)).count();
+            missed += expected.iter().filter(|i| !found.contains(i)).count();
+        }
+        let report = training.reports.last().unwrap();
+        assert_eq!(
+            (report.good, report.bad, report.missed),
+            (good, bad, missed)
+        );
+        assert_eq!(report.good + report.missed, 38);
+
+        let filename = std::env::temp_dir().join("pyphen_rs_patgen.txt");
+        std::fs::write(
+            &filename,
+            "% comment\nta-ble pat-tern\n# comment\nhy-phen\n",
+        )
+        .unwrap();
+        let mut patgen = Patgen::read_file(filename.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&filename).unwrap();
+        let training = patgen.level(level(1..=2, 1)).train();
+        let report = training.reports[0];
+        assert_eq!(report.good + report.missed, 3);
+        assert!(Patgen::read_file("missing.txt").is_err());
+    }
+
+    /// Test that patgen ignores words with characters of the patterns syntax.
+    #[test]
+    fn test_patgen_syntax() {
+        let level = PatgenLevel {
+            lengths: 1..=3,
+            good_weight: 1,
+            bad_weight: 1,
+            threshold: 1,
+        };
+
+        let words = ["x/-b", "y/-b", "xbz", "ybz", "zbx"];
+        let training = Patgen::new(words).level(level.clone()).train();
+        assert_eq!(training.reports[0].good + training.reports[0].missed, 0);
+        assert!(training.hyph_dict().is_ok());
+
+        let words = ["%a-ba", "#a-ba", "a b-ba", "x=y-ba", "ca-ba", "da-ba"];
+        let training = Patgen::new(words).level(level).train();
+        let report = training.reports[0];
+        assert_eq!((report.good, report.bad, report.missed), (2, 0, 0));
+        let dic = Builder::dictionary(Arc::new(training.hyph_dict().unwrap()))
+            .build()
+            .unwrap();
+        assert_eq!(dic.inserted("caba"), "ca-ba");
+        assert_eq!(dic.inserted("daba"), "da-ba");
+    }
+
+    /// Test the problems found in dictionaries.
+    #[test]
+    fn test_validate() {
+        let content = format!(
+            "ISO8859-1\n\
+             LEFTHYPHENMIN 2\n\
+             RIGHTHYPHENMIN\n\
+             COMPOUNDMIN 2\n\
+             1-1\n\
+             NEXTLEVEL\n\
+             a1b\n\
+             a1b\n\
+             a3b\n\
+             c1d11\n\
+             e1f\n\
+             c1k/k=k,1,1\n\
+             c1l/l=l,3,1\n\
+             c2m/m=m,1,1\n\
+             c1n/nn,1,1\n\
+             g1h \n\
+             g1.h\n\
+             G1h\n\
+             {}\n",
+            "x1".repeat(50)
+        );
+        let lints = validate(content.as_bytes(), "hyph_xx.dic").unwrap();
+        let lints: Vec<_> = lints
+            .into_iter()
+            .map(|lint| (lint.line, lint.kind))
+            .collect();
+        assert_eq!(
+            lints,
+            [
+                (
+                    3,
+                    LintKind::InvalidDirective {
+                        directive: "RIGHTHYPHENMIN".to_string()
+                    }
+                ),
+                (
+                    4,
+                    LintKind::UnknownDirective {
+                        directive: "COMPOUNDMIN".to_string()
+                    }
+                ),
+                (9, LintKind::ConflictingDuplicate { line: 8 }),
+                (10, LintKind::MalformedPattern),
+                (13, LintKind::MalformedAlternative),
+                (14, LintKind::MalformedAlternative),
+                (15, LintKind::MalformedAlternative),
+                (16, LintKind::UnreachableCharacter { character: ' ' }),
+                (17, LintKind::UnreachableCharacter { character: '.' }),
+                (18, LintKind::MixedCase),
+                (19, LintKind::TooLong { length: 100 }),
+            ]
+        );
+
+        let lints = validate(&b"a1b\n\xff1b\n"[..], "hyph_xx.dic").unwrap();
+        assert_eq!(lints[0].kind, LintKind::InvalidEncoding);
+        assert_eq!(lints[0].to_string(), "hyph_xx.dic:2: invalid encoding");
+        assert!(validate(&b"ISCII-DEVANAGARI\n"[..], "hyph_xx.dic").is_err());
+    }
+
+    /// Test the comparison with reference hyphenations.
+    #[test]
+    fn test_evaluate() {
+        let dic = Builder::lang("nl_NL").build().unwrap();
+        let references = ["let-ter-gre-pen", "le-tter-gre-pen", "l-ettergrepe-n", ""];
+        let evaluation = dic.evaluate(references);
+        assert_eq!(
+            (evaluation.good, evaluation.bad, evaluation.missed),
+            (5, 4, 1)
+        );
+        assert_eq!(evaluation.words.len(), 2);
+        assert_eq!(evaluation.words[1].bad, [3, 6, 9]);
+        assert!(evaluation.words[1].missed.is_empty());
+        assert_eq!(evaluation.precision(), 5.0 / 9.0);
+        assert_eq!(evaluation.recall(), 5.0 / 6.0);
+
+        // hyphens not allowed by left and right are ignored
+        let dic = Builder::lang("nl_NL").left(3).right(4).build().unwrap();
+        let evaluation = dic.evaluate(&references[..2]);
+        assert_eq!(
+            (evaluation.good, evaluation.bad, evaluation.missed),
+            (3, 1, 0)
+        );
+        assert_eq!(Evaluation::default().precision(), 1.0);
+        assert_eq!(Evaluation::default().recall(), 1.0);
+
+        let filename = std::env::temp_dir().join("pyphen_rs_evaluate.txt");
+        std::fs::write(&filename, "% comment\nlet-ter-gre-pen\nle-tter-gre-pen\n").unwrap();
+        let evaluation = dic.evaluate_file(filename.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&filename).unwrap();
+        assert_eq!(evaluation.words[0].bad, [3]);
+        assert!(dic.evaluate_file("missing.txt").is_err());
+    }
+
+    /// Test the hyphenation of running text.
+    #[test]
+    fn test_text() {
+        let dic = Builder::lang("nl_NL").build().unwrap();
+        let text = " (lettergrepen),\r\n\t«Lettergrepen» 12 3a lettergrepen... ";
+        assert_eq!(
+            dic.inserted_text(text),
+            " (let-ter-gre-pen),\r\n\t«Let-ter-gre-pen» 12 3a let-ter-gre-pen... "
+        );
+        assert_eq!(dic.text_positions("«lettergrepen»"), [5, 8, 11]);
+        assert_eq!(dic.inserted_text(""), "");
+        assert_eq!(dic.inserted_text("?!"), "?!");
+
+        // combining marks stay with their letters
+        let word = "lettergrepe\u{301}";
+        assert_eq!(words(word).collect::<Vec<_>>(), [(0, word)]);
+        let words: Vec<_> = words("e.g. don't 3.14 x-ray").map(|(_, w)| w).collect();
+        assert_eq!(words, ["e.g", "don't", "x", "ray"]);
+
+        // nonstandard hyphenation applies in texts
+        let dic = Builder::lang("hu_HU").left(1).right(1).build().unwrap();
+        assert_eq!(dic.inserted_text("„kulissza”"), "„ku-lisz-sza”");
+    }
+
+    /// Test the hyphenation of the text nodes of HTML documents.
+    #[test]
+    fn test_html() {
+        let dic = Builder::lang("en_US").build().unwrap();
+        let html = |html: &str| dic.inserted_html_with(html, "-");
+
+        // only text nodes are hyphenated
+        assert_eq!(html("hyphenation"), "hy-phen-ation");
+        assert_eq!(
+            html("<a title='hyphenation > wonderful' href=wonderful>wonderful</a>"),
+            "<a title='hyphenation > wonderful' href=wonderful>won-der-ful</a>"
+        );
+        assert_eq!(
+            html("<!-- wonderful --><!DOCTYPE html><?xml wonderful?>a < wonderful"),
+            "<!-- wonderful --><!DOCTYPE html><?xml wonderful?>a < won-der-ful"
+        );
+        assert_eq!(
+            html("wonderful&nbsp;hyphenation &amp; wonder&shy;ful, &#233;"),
+            "wonderful&nbsp;hyphenation &amp; wonder&shy;ful, &#233;"
+        );
+        assert_eq!(
+            html("<P>wonderful<BR>wonderful<img src=x/>wonderful</P>wonderful"),
+            "<P>won-der-ful<BR>won-der-ful<img src=x/>won-der-ful</P>won-der-ful"
+        );
+
+        // skipped elements
+        assert_eq!(
+            html("<script>if (a<b) wonderful();</script><STYLE>wonderful</Style>wonderful"),
+            "<script>if (a<b) wonderful();</script><STYLE>wonderful</Style>won-der-ful"
+        );
+        assert_eq!(
+            html("<pre>wonderful <b>wonderful</b></pre><code>wonderful</code>wonderful"),
+            "<pre>wonderful <b>wonderful</b></pre><code>wonderful</code>won-der-ful"
+        );
+        assert_eq!(
+            html("<p style=\"color: red; hyphens: none\">wonderful <i>wonderful</i></p>"),
+            "<p style=\"color: red; hyphens: none\">wonderful <i>wonderful</i></p>"
+        );
+        assert_eq!(
+            html("<p style='-webkit-hyphens:none'>wonderful <i style='hyphens: manual'>wonderful</i></p>"),
+            "<p style='-webkit-hyphens:none'>wonderful <i style='hyphens: manual'>won-der-ful</i></p>"
+        );
+        assert_eq!(
+            html("<p translate=no>wonderful <i translate=yes>wonderful</i> wonderful</p>"),
+            "<p translate=no>wonderful <i translate=yes>won-der-ful</i> wonderful</p>"
+        );
+        assert_eq!(
+            html("<pre translate=no><i translate=yes style='hyphens: auto'>wonderful</i></pre>"),
+            "<pre translate=no><i translate=yes style='hyphens: auto'>wonderful</i></pre>"
+        );
+
+        // languages
+        assert_eq!(
+            html("<div lang=nl-NL>lettergrepen <i xml:lang=en lang=nl>wonderful</i></div>"),
+            "<div lang=nl-NL>let-ter-gre-pen <i xml:lang=en lang=nl>won-der-ful</i></div>"
+        );
+        assert_eq!(
+            html("<p lang=xx>wonderful</p><p lang=\"\">wonderful</p>wonderful"),
+            "<p lang=xx>wonderful</p><p lang=\"\">wonderful</p>won-der-ful"
+        );
+        assert_eq!(
+            html("<div lang=''>wonderful <p lang=en>wonderful</p></div>"),
+            "<div lang=''>wonderful <p lang=en>won-der-ful</p></div>"
+        );
+
+        // soft hyphens by default
+        assert_eq!(
+            dic.inserted_html("<b>wonderful</b>"),
+            "<b>won\u{ad}der\u{ad}ful</b>"
+        );
+
+        // options of the builder
+        let dic = Builder::lang("en_US")
+            .left(5)
+            .exceptions(vec!["hyphena-tion"])
+            .build()
+            .unwrap();
+        assert_eq!(
+            dic.inserted_html_with("<html lang=en><p>wonderful", "-"),
+            "<html lang=en><p>wonder-ful"
+        );
+        assert_eq!(
+            dic.inserted_html_with("<p lang=en-US>hyphenation</p>", "-"),
+            "<p lang=en-US>hyphena-tion</p>"
+        );
+        assert_eq!(
+            dic.inserted_html_with("<p lang=nl>lettergrepen hyphenation</p>", "-"),
+            "<p lang=nl>letter-gre-pen hyphena-tion</p>"
+        );
+        let dic = Builder::lang("en_US").patterns("o5n").build().unwrap();
+        assert_eq!(
+            dic.inserted_html_with("<p lang=en>wonderful</p>", "-"),
+            "<p lang=en>wo-n-der-ful</p>"
+        );
+    }
+
+    /// Test the hyphenation of the prose of Markdown documents.
+    #[test]
+    fn test_markdown() {
+        let dic = Builder::lang("en_US").build().unwrap();
+        let markdown = |markdown: &str| dic.inserted_markdown_with(markdown, "-");
+
+        // inline
+        assert_eq!(
+            markdown("# Wonderful *wonderful*"),
+            "# Won-der-ful *won-der-ful*"
+        );
+        assert_eq!(
+            markdown("a `wonderful` b ``wonderful ` wonderful`` wonderful `wonderful"),
+            "a `wonderful` b ``wonderful ` wonderful`` won-der-ful `won-der-ful"
+        );
+        assert_eq!(
+            markdown(
+                "[wonderful](http://a.b/(wonderful) \"wonderful\") ![wonderful](wonderful.png)"
+            ),
+            "[won-der-ful](http://a.b/(wonderful) \"wonderful\") ![won-der-ful](wonderful.png)"
+        );
+        assert_eq!(
+            markdown("[wonderful][wonderful] [Wonderful]\n\n[wonderful]: http://a.b/wonderful"),
+            "[won-der-ful][wonderful] [Wonderful]\n\n[wonderful]: http://a.b/wonderful"
+        );
+        assert_eq!(
+            markdown("see https://wonderful.org/wonderful, <https://wonderful.org> or wonderful@mail.org."),
+            "see https://wonderful.org/wonderful, <https://wonderful.org> or wonderful@mail.org."
+        );
+        assert_eq!(
+            markdown("<span title=wonderful>wonderful</span>&nbsp;wonderful &amp; wonderful"),
+            "<span title=wonderful>won-der-ful</span>&nbsp;wonderful &amp; won-der-ful"
+        );
+
+        // blocks
+        assert_eq!(
+            markdown("---\ntitle: wonderful\n---\nwonderful"),
+            "---\ntitle: wonderful\n---\nwon-der-ful"
+        );
+        assert_eq!(
+            markdown("wonderful\n````rust\nwonderful\n```\n\nwonderful\n````\nwonderful"),
+            "won-der-ful\n````rust\nwonderful\n```\n\nwonderful\n````\nwon-der-ful"
+        );
+        assert_eq!(
+            markdown("> ~~~\n> wonderful\n> ~~~\n> wonderful"),
+            "> ~~~\n> wonderful\n> ~~~\n> won-der-ful"
+        );
+        assert_eq!(
+            markdown("wonderful\n    wonderful\n\n    wonderful\n\twonderful\n\nwonderful"),
+            "won-der-ful\n    won-der-ful\n\n    wonderful\n\twonderful\n\nwon-der-ful"
+        );
+        assert_eq!(
+            markdown("<div>\nwonderful\n</div>\n\n<pre>\n\nwonderful\n</pre>\n<!-- wonderful\n-->wonderful"),
+            "<div>\nwonderful\n</div>\n\n<pre>\n\nwonderful\n</pre>\n<!-- wonderful\n-->wonderful"
+        );
+        assert_eq!(
+            markdown("<!-- wonderful -->\nwonderful"),
+            "<!-- wonderful -->\nwon-der-ful"
+        );
+
+        // soft hyphens by default
+        assert_eq!(
+            dic.inserted_markdown("wonderful\r\n"),
+            "won\u{ad}der\u{ad}ful\r\n"
+        );
+    }
+
+    /// Test the greedy wrapping of paragraphs.
+    #[test]
+    fn test_wrap_paragraph() {
+        let dic = Builder::lang("nl_NL").build().unwrap();
+        assert!(dic.wrap_paragraph("", 10).is_empty());
+        assert!(dic.wrap_paragraph(" \n ", 10).is_empty());
+        assert_eq!(dic.wrap_paragraph("een\n\t twee", 10), ["een twee"]);
+        assert_eq!(
+            dic.wrap_paragraph("lettergrepen", 4),
+            ["let-", "ter-", "gre-", "pen"]
+        );
+        assert_eq!(
+            dic.wrap_paragraph("(lettergrepen),", 8),
+            ["(letter-", "grepen),"]
+        );
+        assert_eq!(
+            dic.wrap_paragraph_with("een lettergrepen", 10, "‧‧"),
+            ["een let‧‧", "tergrepen"]
+        );
+
+        // too long words have their own line
+        assert_eq!(
+            dic.wrap_paragraph("a xxxxxxxxxxxx b", 5),
+            ["a", "xxxxxxxxxxxx", "b"]
+        );
+        assert_eq!(dic.wrap_paragraph("een twee", 0), ["een", "twee"]);
+
+        // ranges give the source of the lines
+        let text = "Het aantal  lettergrepen\nvan een woord.";
+        let lines = dic.wrap_paragraph_lines(text, 11, "-");
+        let sources: Vec<_> = lines.iter().map(|line| &text[line.range.clone()]).collect();
+        assert_eq!(
+            sources,
+            ["Het aantal", "lettergre", "pen\nvan een", "woord."]
+        );
+        let hyphenated: Vec<_> = lines.iter().map(|line| line.hyphenated).collect();
+        assert_eq!(hyphenated, [false, true, false, false]);
+
+        // nonstandard hyphenation
+        let dic = Builder::lang("hu_HU").left(1).right(1).build().unwrap();
+        assert_eq!(dic.wrap_paragraph("KULISSZA", 7), ["KULISZ-", "SZA"]);
+        let text = "kulissza";
+        let lines = dic.wrap_paragraph_lines(text, 5, "-");
+        let lines: Vec<_> = lines
+            .iter()
+            .map(|line| (line.text.as_str(), &text[line.range.clone()]))
+            .collect();
+        assert_eq!(lines, [("ku-", "ku"), ("lisz-", "lis"), ("sza", "sza")]);
+
+        // words are only broken at their own positions
+        let dic = Builder::lang("en_US").build().unwrap();
+        assert_eq!(dic.wrap_paragraph("attribute", 5), ["at-", "tribute"]);
+        let dic = Builder::lang("nl_NL").build().unwrap();
+        assert_eq!(
+            dic.wrap_paragraph("development", 4),
+            ["de-", "vel-", "op-", "ment"]
+        );
+        for (lang, word) in &[
+            ("en_US", "hyphenation"),
+            ("en_US", "attribute"),
+            ("nl_NL", "development"),
+            ("nl_NL", "autobandventieldopje"),
+            ("de_DE", "Druckerzeugnis"),
+        ] {
+            let dic = Builder::lang(lang).build().unwrap();
+            let offsets: Vec<_> = dic
+                .positions(word)
+                .iter()
+                .map(|position| position.byte_offset(word))
+                .collect();
+            for width in 1..word.len() {
+                for line in dic.wrap_paragraph_lines(word, width, "-") {
+                    if line.hyphenated {
+                        assert!(offsets.contains(&line.range.end), "{} {}", word, width);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Test the Knuth-Plass breaking of paragraphs.
+    #[test]
+    fn test_break_paragraph() {
+        let dic = Builder::lang("en_US").build().unwrap();
+        let options = KnuthPlass::new();
+        assert!(dic.break_paragraph(" \n", 10, &options).is_empty());
+
+        let text = "Hyphenation is the process of breaking words between lines to \
+                    create more consistency across a text block. In justified text, \
+                    hyphenation is mandatory.";
+        let lines = |breakpoints: &[Breakpoint]| -> Vec<String> {
+            breakpoints.iter().map(|b| b.line.text.clone()).collect()
+        };
+        let breakpoints = dic.break_paragraph(text, 28, &options);
+        assert_eq!(
+            lines(&breakpoints),
+            [
+                "Hyphenation is the process of",
+                "breaking words between lines",
+                "to create more consistency",
+                "across a text block. In jus-",
+                "tified text, hyphenation is",
+                "mandatory."
+            ]
+        );
+        let ratios: Vec<_> = breakpoints
+            .iter()
+            .map(|b| (b.ratio * 100.0).round())
+            .collect();
+        assert_eq!(ratios, [-75.0, 0.0, 133.0, 0.0, 67.0, 0.0]);
+        let hyphenated: Vec<_> = breakpoints.iter().map(|b| b.line.hyphenated).collect();
+        assert_eq!(hyphenated, [false, false, false, true, false, false]);
+        for breakpoint in &breakpoints {
+            let source = &text[breakpoint.line.range.clone()];
+            let words = source.split_whitespace().collect::<Vec<_>>().join(" ");
+            assert!(breakpoint.line.text.starts_with(&words));
+        }
+
+        // infinite hyphen penalty
+        let breakpoints = dic.break_paragraph(text, 28, KnuthPlass::new().hyphen_penalty(10000.0));
+        assert!(breakpoints.iter().all(|b| !b.line.hyphenated));
+        assert_eq!(breakpoints[3].line.text, "across a text block. In");
+
+        // consecutive hyphens
+        let consecutive = |breakpoints: &[Breakpoint]| {
+            breakpoints
+                .windows(2)
+                .filter(|pair| pair[0].line.hyphenated && pair[1].line.hyphenated)
+                .count()
+        };
+        let breakpoints =
+            dic.break_paragraph(text, 38, KnuthPlass::new().double_hyphen_demerits(0.0));
+        assert_eq!(consecutive(&breakpoints), 1);
+        let breakpoints =
+            dic.break_paragraph(text, 38, KnuthPlass::new().double_hyphen_demerits(1e9));
+        assert_eq!(consecutive(&breakpoints), 0);
+
+        // looseness
+        let breakpoints = dic.break_paragraph(text, 20, &options);
+        assert_eq!(breakpoints.len(), 8);
+        let breakpoints = dic.break_paragraph(text, 20, KnuthPlass::new().looseness(1));
+        assert_eq!(breakpoints.len(), 9);
+        let breakpoints = dic.break_paragraph(text, 28, KnuthPlass::new().looseness(-1));
+        assert_eq!(breakpoints.len(), 6);
+
+        // tolerance
+        let breakpoints = dic.break_paragraph(text, 20, &options);
+        assert!(breakpoints.iter().any(|b| b.ratio > 2.0));
+        let breakpoints = dic.break_paragraph(text, 20, KnuthPlass::new().tolerance(3.0));
+        assert!(breakpoints.iter().all(|b| b.ratio <= 3.0));
+        let breakpoints = dic.break_paragraph("a xxxxxxxxxxxx b", 5, &options);
+        assert_eq!(lines(&breakpoints), ["a", "xxxxxxxxxxxx", "b"]);
+        assert_eq!(breakpoints[0].ratio, f64::INFINITY);
+        assert_eq!(breakpoints[1].ratio, f64::NEG_INFINITY);
+
+        // nonstandard hyphenation
+        let dic = Builder::lang("hu_HU").left(1).right(1).build().unwrap();
+        let text = "a kulissza ott";
+        let breakpoints = dic.break_paragraph(text, 9, &options);
+        assert_eq!(lines(&breakpoints), ["a kulisz-", "sza ott"]);
+        let sources: Vec<_> = breakpoints
+            .iter()
+            .map(|b| &text[b.line.range.clone()])
+            .collect();
+        assert_eq!(sources, ["a kulis", "sza ott"]);
+    }
 }
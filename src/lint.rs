@@ -0,0 +1,266 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! Validation of ``hyph_*.dic`` dictionaries.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
+
+use super::charset::Charset;
+use super::hyph_dict::{lowercase, parse_pattern, read_lines};
+use super::{AlternativeParser, DataInt, Error, Metadata};
+
+/// Size of the lines read by libhyphen, longer lines are cut.
+const MAX_LINE: usize = 99;
+
+/// Directives taking a number.
+const NUMERIC_DIRECTIVES: [&str; 4] = [
+    "LEFTHYPHENMIN",
+    "RIGHTHYPHENMIN",
+    "COMPOUNDLEFTHYPHENMIN",
+    "COMPOUNDRIGHTHYPHENMIN",
+];
+
+/// Kind of problem found in a dictionary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LintKind {
+    /// The line is not valid in the character set of the dictionary.
+    InvalidEncoding,
+    /// The pattern cannot be parsed, or has consecutive digits giving values
+    /// after its last letter.
+    MalformedPattern,
+    /// The letters of the pattern are already given, with other values, by
+    /// a pattern of the same level. The last one is used.
+    ConflictingDuplicate {
+        /// Line of the previous pattern, starting at 1.
+        line: usize,
+    },
+    /// The line is longer than what libhyphen reads, in bytes.
+    TooLong {
+        /// Length of the line, in bytes of the character set.
+        length: usize,
+    },
+    /// The line starts with an unknown directive, read as a pattern.
+    UnknownDirective {
+        /// Name of the directive.
+        directive: String,
+    },
+    /// The value of the directive is missing or is not a number.
+    InvalidDirective {
+        /// Name of the directive.
+        directive: String,
+    },
+    /// The ``/change,index,cut`` alternative cannot be parsed, is outside of
+    /// the letters of the pattern, or has no odd value to apply to.
+    MalformedAlternative,
+    /// The pattern has a character that words never have once split:
+    /// whitespace, control characters or dots between letters.
+    UnreachableCharacter {
+        /// The first unreachable character.
+        character: char,
+    },
+    /// The pattern has uppercase letters, that never match as words are
+    /// lowercased before being hyphenated.
+    MixedCase,
+}
+
+/// Problem found in a line of a dictionary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    /// Filename of the dictionary.
+    pub filename: String,
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// Text of the line.
+    pub text: String,
+    /// Kind of problem.
+    pub kind: LintKind,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: ", self.filename, self.line)?;
+        match &self.kind {
+            LintKind::InvalidEncoding => write!(f, "invalid encoding"),
+            LintKind::MalformedPattern => write!(f, "malformed pattern {:?}", self.text),
+            LintKind::ConflictingDuplicate { line } => write!(
+                f,
+                "pattern {:?} conflicts with the pattern of line {}",
+                self.text, line
+            ),
+            LintKind::TooLong { length } => write!(
+                f,
+                "line of {} bytes, longer than the {} bytes read by libhyphen",
+                length, MAX_LINE
+            ),
+            LintKind::UnknownDirective { directive } => {
+                write!(f, "unknown directive {:?}", directive)
+            }
+            LintKind::InvalidDirective { directive } => {
+                write!(f, "invalid value for directive {:?}", directive)
+            }
+            LintKind::MalformedAlternative => {
+                write!(f, "malformed alternative in pattern {:?}", self.text)
+            }
+            LintKind::UnreachableCharacter { character } => write!(
+                f,
+                "unreachable character {:?} in pattern {:?}",
+                character, self.text
+            ),
+            LintKind::MixedCase => write!(f, "uppercase letters in pattern {:?}", self.text),
+        }
+    }
+}
+
+/// Check a dictionary in the ``hyph_*.dic`` format.
+///
+/// Lines are checked independently, all the problems found are returned,
+/// in the order of the lines.
+///
+/// Returns `Err` if the dictionary cannot be read or declares an
+/// unsupported character set.
+///
+/// # Example
+/// ```
+/// use pyphen_rs::{validate, LintKind};
+///
+/// let lints = validate(&b"UTF-8\nLEFTHYPHENMIN two\na1b\na2b\n"[..], "hyph_xx.dic").unwrap();
+/// assert_eq!(lints.len(), 2);
+/// assert_eq!(lints[0].line, 2);
+/// assert_eq!(lints[1].kind, LintKind::ConflictingDuplicate { line: 3 });
+/// assert_eq!(lints[1].to_string(), "hyph_xx.dic:4: pattern \"a2b\" conflicts with the pattern of line 3");
+/// ```
+pub fn validate<R: BufRead>(stream: R, filename: &str) -> Result<Vec<Lint>, Error> {
+    let mut lints = Vec::new();
+    let mut patterns: HashMap<String, (usize, (usize, Vec<DataInt>))> = HashMap::new();
+
+    let (charset, lines) = read_lines(stream, filename)?;
+    for (line, text) in lines {
+        let mut lint = |text: &str, kind| {
+            lints.push(Lint {
+                filename: filename.to_string(),
+                line,
+                text: text.to_string(),
+                kind,
+            })
+        };
+
+        let text = match text {
+            Some(text) => text,
+            None => {
+                lint("", LintKind::InvalidEncoding);
+                continue;
+            }
+        };
+        let length = match charset {
+            Charset::Utf8 => text.len(),
+            _ => text.chars().count(),
+        };
+        if length > MAX_LINE {
+            lint(&text, LintKind::TooLong { length });
+        }
+
+        if text.is_empty() || text.starts_with('%') || text.starts_with('#') {
+            continue;
+        }
+
+        // directives are checked like the parser reads them
+        let directive = text.split_whitespace().next().unwrap_or_default();
+        if Metadata::default().parse_line(&text) != Ok(false) {
+            let value = text.split_whitespace().nth(1);
+            let valid = match NUMERIC_DIRECTIVES.contains(&directive) {
+                true => value
+                    .and_then(|value| value.parse::<usize>().ok())
+                    .is_some(),
+                false => value.is_some(),
+            };
+            if !valid {
+                let directive = directive.to_string();
+                lint(&text, LintKind::InvalidDirective { directive });
+            }
+            continue;
+        }
+        if text.starts_with("NEXTLEVEL") {
+            patterns.clear();
+            continue;
+        }
+        if directive.len() > 1
+            && directive
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c == '_')
+        {
+            let directive = directive.to_string();
+            lint(&text, LintKind::UnknownDirective { directive });
+            continue;
+        }
+
+        let (pattern, alternative) = match text.split_once('/') {
+            Some((pattern, alternative)) => (pattern, Some(alternative)),
+            None => (text.as_str(), None),
+        };
+        let parsed = match parse_pattern(pattern, charset) {
+            Ok(Some(parsed)) => parsed,
+            Ok(None) => continue,
+            Err(()) => {
+                lint(&text, LintKind::MalformedPattern);
+                continue;
+            }
+        };
+        let (tags, (offset, values)) = &parsed;
+        let letters: Vec<_> = tags.chars().collect();
+        if offset + values.len() > letters.len() + 1 {
+            lint(&text, LintKind::MalformedPattern);
+        }
+
+        if let Some(alternative) = alternative {
+            let valid = AlternativeParser::new(tags, alternative).is_some()
+                && values.iter().any(|value| value.value % 2 == 1);
+            if !valid {
+                lint(&text, LintKind::MalformedAlternative);
+            }
+        }
+
+        let last = letters.len().saturating_sub(1);
+        let unreachable = letters.iter().enumerate().find(|&(index, &c)| {
+            c.is_whitespace() || c.is_control() || (c == '.' && index != 0 && index != last)
+        });
+        if let Some((_, &character)) = unreachable {
+            lint(&text, LintKind::UnreachableCharacter { character });
+        }
+        if lowercase(tags) != *tags {
+            lint(&text, LintKind::MixedCase);
+        }
+
+        // the parsed values of the alternative are compared too
+        let (tags, values) = match alternative {
+            Some(_) => match parse_pattern(&text, charset) {
+                Ok(Some(parsed)) => parsed,
+                _ => parsed,
+            },
+            None => parsed,
+        };
+        match patterns.get(&tags) {
+            Some((previous, previous_values)) if *previous_values != values => {
+                let line = *previous;
+                lint(&text, LintKind::ConflictingDuplicate { line });
+            }
+            _ => (),
+        }
+        patterns.insert(tags, (line, values));
+    }
+
+    Ok(lints)
+}
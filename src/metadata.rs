@@ -0,0 +1,79 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+/// Header directives read from a ``hyph_*.dic`` file.
+///
+/// Each field is ``None``, or empty, if the dictionary does not declare the
+/// directive.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// Value of the ``LEFTHYPHENMIN`` directive.
+    pub left_hyphen_min: Option<usize>,
+    /// Value of the ``RIGHTHYPHENMIN`` directive.
+    pub right_hyphen_min: Option<usize>,
+    /// Value of the ``COMPOUNDLEFTHYPHENMIN`` directive.
+    pub compound_left_hyphen_min: Option<usize>,
+    /// Value of the ``COMPOUNDRIGHTHYPHENMIN`` directive.
+    pub compound_right_hyphen_min: Option<usize>,
+    /// Strings of the ``NOHYPHEN`` directive, next to which no hyphen is
+    /// inserted.
+    pub no_hyphen: Vec<String>,
+}
+
+impl Metadata {
+    /// Replace the directives declared by the other metadata.
+    pub(crate) fn update(&mut self, other: Metadata) {
+        self.left_hyphen_min = other.left_hyphen_min.or(self.left_hyphen_min);
+        self.right_hyphen_min = other.right_hyphen_min.or(self.right_hyphen_min);
+        self.compound_left_hyphen_min = other
+            .compound_left_hyphen_min
+            .or(self.compound_left_hyphen_min);
+        self.compound_right_hyphen_min = other
+            .compound_right_hyphen_min
+            .or(self.compound_right_hyphen_min);
+        if !other.no_hyphen.is_empty() {
+            self.no_hyphen = other.no_hyphen;
+        }
+    }
+
+    /// Read a directive line into the metadata.
+    ///
+    /// Returns ``false`` if the line is not a known directive and should be
+    /// parsed as a pattern, and ``Err`` if the value of a numeric directive
+    /// is missing or is not a number.
+    pub(crate) fn parse_line(&mut self, line: &str) -> Result<bool, ()> {
+        let mut parts = line.split_whitespace();
+        let field = match parts.next() {
+            Some("LEFTHYPHENMIN") => &mut self.left_hyphen_min,
+            Some("RIGHTHYPHENMIN") => &mut self.right_hyphen_min,
+            Some("COMPOUNDLEFTHYPHENMIN") => &mut self.compound_left_hyphen_min,
+            Some("COMPOUNDRIGHTHYPHENMIN") => &mut self.compound_right_hyphen_min,
+            Some("NOHYPHEN") => {
+                self.no_hyphen = parts
+                    .next()
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|string| !string.is_empty())
+                    .map(String::from)
+                    .collect();
+                return Ok(true);
+            }
+            _ => return Ok(false),
+        };
+        *field = Some(parts.next().ok_or(())?.parse().map_err(|_| ())?);
+
+        Ok(true)
+    }
+}
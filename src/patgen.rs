@@ -0,0 +1,344 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! Generation of hyphenation patterns from hyphenated words, as done by
+//! TeX's ``patgen``.
+//!
+//! Patterns are generated level by level: odd levels give patterns allowing
+//! the hyphens missed so far, even levels give patterns inhibiting the wrong
+//! hyphens found so far. At each level, the candidate patterns are the
+//! substrings of the words around the positions to fix, from the shortest
+//! to the longest. A candidate fixing *good* positions and breaking *bad*
+//! positions is kept when ``good * good_weight >= bad * bad_weight +
+//! threshold``.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+
+use super::exceptions::{read_words, split_hyphens};
+use super::hyph_dict::lowercase;
+use super::{Error, HyphDict};
+
+/// Values of the patterns are single digits.
+const MAX_LEVEL: usize = 9;
+
+/// Parameters of a level of generated patterns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatgenLevel {
+    /// Lengths of the candidate patterns, counting the dots marking the
+    /// beginning and the end of the words.
+    pub lengths: RangeInclusive<usize>,
+    /// Weight of the positions fixed by a pattern.
+    pub good_weight: usize,
+    /// Weight of the positions broken by a pattern.
+    pub bad_weight: usize,
+    /// Minimum score of the kept patterns.
+    pub threshold: usize,
+}
+
+/// Hyphens of the words once the patterns of a level are generated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PatgenReport {
+    /// Level of the patterns, from 1.
+    pub level: usize,
+    /// Number of patterns generated for this level.
+    pub patterns: usize,
+    /// Number of hyphens correctly found.
+    pub good: usize,
+    /// Number of hyphens wrongly found.
+    pub bad: usize,
+    /// Number of hyphens not found.
+    pub missed: usize,
+}
+
+/// Patterns generated from hyphenated words.
+#[derive(Clone, Debug)]
+pub struct Training {
+    /// The generated dictionary, in the ``hyph_*.dic`` format.
+    pub dictionary: String,
+    /// Hyphens of the words after each level.
+    pub reports: Vec<PatgenReport>,
+}
+
+impl Training {
+    /// Load the generated dictionary.
+    ///
+    /// Returns `Err` if the generated patterns cannot be parsed.
+    pub fn hyph_dict(&self) -> Result<HyphDict, Error> {
+        HyphDict::read(self.dictionary.as_bytes(), "<patgen>")
+    }
+}
+
+/// Hyphenated word, with the state of its hyphenation.
+struct Word {
+    /// Letters of the lowercase word, between dots.
+    letters: Vec<char>,
+    /// Whether there is a hyphen at each position, a position being before
+    /// each letter or at the end.
+    hyphens: Vec<bool>,
+    /// Values given by the generated patterns at each position.
+    values: Vec<usize>,
+    /// Whether a pattern of the current level matches at each position.
+    done: Vec<bool>,
+}
+
+/// Generator of hyphenation patterns from hyphenated words.
+///
+/// # Example
+/// ```
+/// use pyphen_rs::{Builder, Patgen, PatgenLevel};
+/// use std::sync::Arc;
+///
+/// let words = ["hy-phen-ation", "pat-tern", "dic-tio-nary", "ta-ble"];
+/// let training = Patgen::new(&words)
+///     .level(PatgenLevel { lengths: 2..=5, good_weight: 1, bad_weight: 1, threshold: 1 })
+///     .level(PatgenLevel { lengths: 2..=5, good_weight: 1, bad_weight: 1, threshold: 1 })
+///     .train();
+/// let report = training.reports.last().unwrap();
+/// assert_eq!((report.good, report.bad, report.missed), (6, 0, 0));
+///
+/// let dic = Builder::dictionary(Arc::new(training.hyph_dict().unwrap())).build().unwrap();
+/// assert_eq!(dic.inserted("hyphenation"), "hy-phen-ation");
+/// ```
+pub struct Patgen {
+    words: Vec<(String, Vec<usize>)>,
+    left: usize,
+    right: usize,
+    levels: Vec<PatgenLevel>,
+}
+
+impl Patgen {
+    /// Create a generator for words hyphenated as in ``ta-ble``.
+    ///
+    /// Words are lowercased, words with digits, dots, whitespace or
+    /// characters with a meaning in dictionaries (``/``, ``=``, ``%``, ``#``
+    /// and ``^``) are ignored.
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut patgen = Self {
+            words: Vec::new(),
+            left: 2,
+            right: 2,
+            levels: Vec::new(),
+        };
+        for word in words {
+            patgen.insert(word.as_ref());
+        }
+
+        patgen
+    }
+
+    /// Create a generator for the words of a file, separated by whitespace.
+    ///
+    /// Lines starting with ``%`` or ``#`` are comments.
+    ///
+    /// Returns `Err` if the file cannot be read.
+    pub fn read_file(filename: &str) -> Result<Self, Error> {
+        Ok(Self::new(read_words(filename)?))
+    }
+
+    /// Set the minimum number of characters in the first syllable, no
+    /// hyphen is considered before. Default is 2.
+    pub fn left(&mut self, left: usize) -> &mut Self {
+        self.left = left.max(1);
+        self
+    }
+
+    /// Set the minimum number of characters in the last syllable, no hyphen
+    /// is considered after. Default is 2.
+    pub fn right(&mut self, right: usize) -> &mut Self {
+        self.right = right.max(1);
+        self
+    }
+
+    /// Add a level of patterns. Only the first 9 levels are generated.
+    pub fn level(&mut self, level: PatgenLevel) -> &mut Self {
+        self.levels.push(level);
+        self
+    }
+
+    /// Generate the patterns of the levels.
+    pub fn train(&self) -> Training {
+        let mut words: Vec<_> = self
+            .words
+            .iter()
+            .map(|(word, hyphens)| {
+                let letters: Vec<_> = format!(".{}.", word).chars().collect();
+                // the hyphen before the nth letter of the word is at the
+                // position before its nth letter once dotted
+                let mut positions = vec![false; letters.len() + 1];
+                for &hyphen in hyphens {
+                    positions[hyphen + 1] = true;
+                }
+                Word {
+                    values: vec![0; positions.len()],
+                    done: vec![false; positions.len()],
+                    hyphens: positions,
+                    letters,
+                }
+            })
+            .collect();
+
+        let mut patterns: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut reports = Vec::new();
+        for (index, parameters) in self.levels.iter().take(MAX_LEVEL).enumerate() {
+            let level = index + 1;
+            // odd levels allow hyphens, even levels inhibit them
+            let hyphen = level % 2 == 1;
+            for word in &mut words {
+                word.done.iter_mut().for_each(|done| *done = false);
+            }
+
+            let mut count = 0;
+            for length in parameters.lengths.clone().filter(|&length| length > 0) {
+                for dot in 0..=length {
+                    let selected = self.select(&words, parameters, hyphen, length, dot);
+                    if selected.is_empty() {
+                        continue;
+                    }
+
+                    for word in &mut words {
+                        let end = word.letters.len().saturating_sub(length - 1);
+                        for start in 0..end {
+                            if selected.contains(&word.letters[start..start + length]) {
+                                let value = &mut word.values[start + dot];
+                                *value = level.max(*value);
+                                word.done[start + dot] = true;
+                            }
+                        }
+                    }
+                    count += selected.len();
+                    for letters in selected {
+                        let values = patterns
+                            .entry(letters.into_iter().collect())
+                            .or_insert_with(|| vec![0; length + 1]);
+                        values[dot] = level;
+                    }
+                }
+            }
+
+            let mut report = PatgenReport {
+                level,
+                patterns: count,
+                ..PatgenReport::default()
+            };
+            for word in &words {
+                for position in self.positions(word) {
+                    match (word.values[position] % 2 == 1, word.hyphens[position]) {
+                        (true, true) => report.good += 1,
+                        (true, false) => report.bad += 1,
+                        (false, true) => report.missed += 1,
+                        (false, false) => (),
+                    }
+                }
+            }
+            reports.push(report);
+        }
+
+        let mut patterns: Vec<_> = patterns.into_iter().collect();
+        patterns.sort();
+        let mut dictionary = format!(
+            "UTF-8\nLEFTHYPHENMIN {}\nRIGHTHYPHENMIN {}\n",
+            self.left, self.right
+        );
+        for (letters, values) in patterns {
+            for (value, c) in values
+                .iter()
+                .zip(letters.chars().map(Some).chain(Some(None)))
+            {
+                if *value > 0 {
+                    dictionary += &value.to_string();
+                }
+                dictionary.extend(c);
+            }
+            dictionary.push('\n');
+        }
+
+        Training {
+            dictionary,
+            reports,
+        }
+    }
+
+    /// Add a hyphenated word.
+    fn insert(&mut self, word: &str) {
+        // these characters would be read as values, comments or
+        // nonstandard hyphenation in the generated patterns
+        if word.contains(|c: char| {
+            c.is_ascii_digit() || c.is_whitespace() || ['.', '/', '=', '%', '#', '^'].contains(&c)
+        }) {
+            return;
+        }
+
+        let (word, hyphens) = split_hyphens(word);
+        if !word.is_empty() {
+            self.words.push((lowercase(&word), hyphens));
+        }
+    }
+
+    /// Get the positions of a dotted word where hyphens are considered.
+    fn positions(&self, word: &Word) -> RangeInclusive<usize> {
+        let length = word.letters.len() - 2;
+        (self.left + 1)..=(length + 1).saturating_sub(self.right)
+    }
+
+    /// Get the candidate patterns of a given length whose value is at the
+    /// given position in the pattern, and that score above the threshold.
+    fn select(
+        &self,
+        words: &[Word],
+        parameters: &PatgenLevel,
+        hyphen: bool,
+        length: usize,
+        dot: usize,
+    ) -> HashSet<Vec<char>> {
+        let mut counts: HashMap<&[char], (usize, usize)> = HashMap::new();
+        for word in words {
+            for position in self.positions(word) {
+                // only the positions with a wrong hyphenation so far, and
+                // not already fixed by a pattern of this level, are counted
+                let found = word.values[position] % 2 == 1;
+                if word.done[position] || found == hyphen || position < dot {
+                    continue;
+                }
+                let start = position - dot;
+                if start + length > word.letters.len() {
+                    continue;
+                }
+                let count = counts
+                    .entry(&word.letters[start..start + length])
+                    .or_default();
+                if word.hyphens[position] == hyphen {
+                    count.0 += 1;
+                } else {
+                    count.1 += 1;
+                }
+            }
+        }
+
+        counts
+            .into_iter()
+            .filter(|&(_, (good, bad))| {
+                good > 0
+                    && good * parameters.good_weight
+                        >= bad * parameters.bad_weight + parameters.threshold
+            })
+            .map(|(letters, _)| letters.to_vec())
+            .collect()
+    }
+}
@@ -0,0 +1,183 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! Tries of hyphenation patterns.
+//!
+//! The letters of the patterns are stored on the edges of a trie and their
+//! values on the nodes where they end, so that all the patterns starting at
+//! a position of a word are found in a single scan of the following
+//! characters.
+
+use std::collections::BTreeMap;
+
+use super::DataInt;
+
+/// A pattern, with its letters, the offset of its first value and its values.
+pub type Pattern = (String, (usize, Vec<DataInt>));
+
+/// A trie of patterns, whose root is its first node.
+pub trait Trie {
+    /// Get the node following the edge with the character, if any.
+    fn child(&self, node: usize, c: char) -> Option<usize>;
+
+    /// Get the edges of a node, as characters and nodes.
+    fn children(&self, node: usize) -> Vec<(char, usize)>;
+
+    /// Get the offset of the first value and the values of a node.
+    fn values(&self, node: usize) -> (usize, Vec<DataInt>);
+
+    /// Raise the references to the values of a node, if they are higher.
+    ///
+    /// - *start* - position of the first value in the references
+    fn apply(&self, node: usize, start: usize, references: &mut [DataInt]);
+
+    /// Get the values of the patterns matching the pointed word, by
+    /// character position.
+    fn references(&self, pointed_word: &str) -> Vec<DataInt> {
+        let pointed_word: Vec<_> = pointed_word.chars().collect();
+        let length = pointed_word.len();
+        let mut references = vec![DataInt::new(0, None); length + 1];
+
+        for i in 0..(length - 1) {
+            let mut node = 0;
+            for &c in &pointed_word[i..] {
+                node = match self.child(node, c) {
+                    Some(child) => child,
+                    None => break,
+                };
+                self.apply(node, i, &mut references);
+            }
+        }
+
+        references
+    }
+
+    /// Get all the patterns of the trie.
+    fn patterns(&self) -> Vec<Pattern> {
+        let mut patterns = Vec::new();
+        let mut stack = vec![(String::new(), 0)];
+        while let Some((tags, node)) = stack.pop() {
+            let (offset, values) = self.values(node);
+            for (c, child) in self.children(node) {
+                let mut tags = tags.clone();
+                tags.push(c);
+                stack.push((tags, child));
+            }
+            if !values.is_empty() {
+                patterns.push((tags, (offset, values)));
+            }
+        }
+
+        patterns
+    }
+}
+
+/// A node of a trie, as ranges of its edges and of its values.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Node {
+    pub first_edge: usize,
+    pub edge_count: usize,
+    pub first_value: usize,
+    pub value_count: usize,
+    /// Offset of the first value of the pattern.
+    pub offset: usize,
+}
+
+/// A single level of hyphenation patterns, stored in memory.
+#[derive(Clone)]
+pub struct Patterns {
+    pub nodes: Vec<Node>,
+    /// Characters and nodes of the edges, sorted by character for each node.
+    pub edges: Vec<(char, usize)>,
+    pub values: Vec<DataInt>,
+}
+
+impl Patterns {
+    /// Build the trie of the patterns.
+    ///
+    /// The patterns are sorted first, so that the same patterns always give
+    /// the same trie.
+    pub fn new<I: IntoIterator<Item = Pattern>>(patterns: I) -> Self {
+        let mut patterns: Vec<_> = patterns.into_iter().collect();
+        patterns.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut children = vec![BTreeMap::new()];
+        let mut values = vec![None];
+        for (tags, (offset, pattern_values)) in patterns {
+            let mut node = 0;
+            for c in tags.chars() {
+                let next = children.len();
+                node = *children[node].entry(c).or_insert(next);
+                if node == next {
+                    children.push(BTreeMap::new());
+                    values.push(None);
+                }
+            }
+            values[node] = Some((offset, pattern_values));
+        }
+
+        let mut trie = Self {
+            nodes: Vec::with_capacity(children.len()),
+            edges: Vec::new(),
+            values: Vec::new(),
+        };
+        for (children, values) in children.into_iter().zip(values) {
+            let (offset, values) = values.unwrap_or_default();
+            trie.nodes.push(Node {
+                first_edge: trie.edges.len(),
+                edge_count: children.len(),
+                first_value: trie.values.len(),
+                value_count: values.len(),
+                offset,
+            });
+            trie.edges.extend(children);
+            trie.values.extend(values);
+        }
+
+        trie
+    }
+}
+
+impl Trie for Patterns {
+    fn child(&self, node: usize, c: char) -> Option<usize> {
+        let node = &self.nodes[node];
+        let edges = &self.edges[node.first_edge..node.first_edge + node.edge_count];
+        edges
+            .binary_search_by_key(&c, |&(c, _)| c)
+            .ok()
+            .map(|index| edges[index].1)
+    }
+
+    fn children(&self, node: usize) -> Vec<(char, usize)> {
+        let node = &self.nodes[node];
+        self.edges[node.first_edge..node.first_edge + node.edge_count].to_vec()
+    }
+
+    fn values(&self, node: usize) -> (usize, Vec<DataInt>) {
+        let node = &self.nodes[node];
+        let values = &self.values[node.first_value..node.first_value + node.value_count];
+        (node.offset, values.to_vec())
+    }
+
+    fn apply(&self, node: usize, start: usize, references: &mut [DataInt]) {
+        let node = &self.nodes[node];
+        let values = &self.values[node.first_value..node.first_value + node.value_count];
+        for (x, y) in references.iter_mut().skip(start + node.offset).zip(values) {
+            if y.value > x.value {
+                *x = y.clone();
+            }
+        }
+    }
+}
@@ -13,49 +13,185 @@
 // FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
 // details.
 
+use std::io::{self, Read};
 use std::ops::Deref;
-use std::rc::Rc;
+use std::sync::{Arc, PoisonError};
 
-use super::{HyphDict, Pyphen};
-use crate::{language_fallback, HD_CACHE, LANGUAGES};
+use super::{Cache, CachePolicy, Exceptions, HyphDict, Pyphen};
+use crate::{
+    embedded, fallback_chain, included_file, language_fallback, Error, ExceptionSource,
+    PatternSource, Source, LANGUAGES,
+};
+
+/// Name of the dictionaries given as strings, bytes or readers, used in
+/// errors.
+const MEMORY: &str = "<memory>";
 
 /// Builder struct to create a hyphenation instance
 pub struct Builder<T> {
-    left: usize,
-    right: usize,
+    left: Option<usize>,
+    right: Option<usize>,
+    compound_left: Option<usize>,
+    compound_right: Option<usize>,
     cache: bool,
+    cache_policy: CachePolicy,
     filename: T,
-    error: bool,
+    embedded: Option<&'static str>,
+    dictionary: Option<Arc<HyphDict>>,
+    content: Option<Vec<u8>>,
+    read_error: Option<io::Error>,
+    patterns: Vec<PatternSource>,
+    exceptions: Vec<ExceptionSource>,
+    unknown_lang: Option<String>,
+    lang: Option<String>,
+}
+
+/// Options given to the builder of a hyphenation instance that do not depend
+/// on its dictionary.
+#[derive(Clone)]
+pub(super) struct Options {
+    left: Option<usize>,
+    right: Option<usize>,
+    compound_left: Option<usize>,
+    compound_right: Option<usize>,
+    cache: bool,
+    cache_policy: CachePolicy,
 }
 
-impl Builder<Rc<String>> {
+impl Builder<Arc<String>> {
     /// Constructs a new Builder for a given language
     ///
+    /// The files listed in ``LANGUAGES`` are used, unless they are included
+    /// dictionaries embedded in the crate.
+    ///
     /// - *lang* - lang of the included dict to use if no filename is given
     pub fn lang(lang: &str) -> Self {
-        let mut filename = None;
-        let mut error = false;
-        LANGUAGES.with(|l| {
-            if let Some(fallback) = language_fallback(lang) {
-                if let Some(cpy) = l.borrow().get(&fallback) {
-                    filename = Some(Rc::clone(&cpy));
-                } else {
-                    error = true;
-                }
-            } else {
-                error = true;
-            }
+        let fallback = language_fallback(lang);
+        let filename = fallback.as_ref().and_then(|fallback| {
+            let languages = LANGUAGES.read().unwrap_or_else(PoisonError::into_inner);
+            languages.get(fallback).cloned()
         });
+        let embedded = fallback
+            .as_ref()
+            .filter(|fallback| {
+                filename
+                    .as_ref()
+                    .map_or(true, |filename| **filename == included_file(fallback))
+            })
+            .and_then(|fallback| embedded(fallback))
+            .map(|(lang, _)| lang);
+        let filename = filename.filter(|_| embedded.is_none());
+        let unknown_lang = match (embedded, &filename) {
+            (None, None) => Some(lang.to_string()),
+            _ => None,
+        };
         let filename = filename.unwrap_or_default();
 
         Self {
             filename,
-            left: 2,
-            right: 2,
+            embedded,
+            dictionary: None,
+            content: None,
+            read_error: None,
+            left: None,
+            right: None,
+            compound_left: None,
+            compound_right: None,
+            cache: true,
+            cache_policy: CachePolicy::default(),
+            patterns: Vec::new(),
+            exceptions: Vec::new(),
+            unknown_lang,
+            lang: fallback,
+        }
+    }
+
+    /// Constructs a new Builder for a loaded dictionary
+    ///
+    /// - *dictionary* - dictionary to use, for example a compiled dictionary
+    ///   loaded with ``HyphDict::from_compiled``
+    pub fn dictionary(dictionary: Arc<HyphDict>) -> Self {
+        Self {
+            filename: Arc::default(),
+            embedded: None,
+            dictionary: Some(dictionary),
+            content: None,
+            read_error: None,
+            left: None,
+            right: None,
+            compound_left: None,
+            compound_right: None,
+            cache: true,
+            cache_policy: CachePolicy::default(),
+            patterns: Vec::new(),
+            exceptions: Vec::new(),
+            unknown_lang: None,
+            lang: None,
+        }
+    }
+
+    /// Constructs a new Builder for the content of a dictionary
+    ///
+    /// The content is parsed when the hyphenation instance is built, with
+    /// the parser of ``hyph_*.dic`` files.
+    ///
+    /// - *patterns* - content of the dictionary
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::from_str("LEFTHYPHENMIN 1\n1ba\n")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(dic.inserted("bababa"), "ba-ba-ba");
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(patterns: &str) -> Self {
+        Self::from_bytes(patterns)
+    }
+
+    /// Constructs a new Builder for the content of a dictionary, as bytes
+    ///
+    /// The bytes can also be a compiled dictionary, see
+    /// ``HyphDict::compile``.
+    ///
+    /// - *bytes* - content of the dictionary
+    pub fn from_bytes<B: Into<Vec<u8>>>(bytes: B) -> Self {
+        Self {
+            filename: Arc::default(),
+            embedded: None,
+            dictionary: None,
+            content: Some(bytes.into()),
+            read_error: None,
+            left: None,
+            right: None,
+            compound_left: None,
+            compound_right: None,
             cache: true,
-            error,
+            cache_policy: CachePolicy::default(),
+            patterns: Vec::new(),
+            exceptions: Vec::new(),
+            unknown_lang: None,
+            lang: None,
         }
     }
+
+    /// Constructs a new Builder for a dictionary read from a reader
+    ///
+    /// The reader is read until its end, errors are returned when the
+    /// hyphenation instance is built.
+    ///
+    /// - *reader* - reader of the content of the dictionary, for example a
+    ///   file in an archive
+    pub fn from_reader<R: Read>(mut reader: R) -> Self {
+        let mut bytes = Vec::new();
+        let read_error = reader.read_to_end(&mut bytes).err();
+        let mut builder = Self::from_bytes(bytes);
+        builder.read_error = read_error;
+        builder
+    }
 }
 
 impl<T> Builder<T> {
@@ -65,30 +201,159 @@ impl<T> Builder<T> {
     pub fn filename(filename: T) -> Self {
         Self {
             filename,
-            left: 2,
-            right: 2,
+            left: None,
+            right: None,
+            compound_left: None,
+            compound_right: None,
             cache: true,
-            error: false,
+            cache_policy: CachePolicy::default(),
+            patterns: Vec::new(),
+            exceptions: Vec::new(),
+            embedded: None,
+            dictionary: None,
+            content: None,
+            read_error: None,
+            unknown_lang: None,
+            lang: None,
         }
     }
 
-    /// Sets the minimum number of characters in the first syllable
+    /// Sets the minimum number of user-perceived characters in the first
+    /// syllable
+    ///
+    /// Defaults to the ``LEFTHYPHENMIN`` directive of the dictionary, or 2
+    /// if there is none.
     pub fn left(&mut self, left: usize) -> &mut Self {
-        self.left = left;
+        self.left = Some(left);
         self
     }
 
-    /// Sets the minimum number of characters in the last syllable
+    /// Sets the minimum number of user-perceived characters in the last
+    /// syllable
+    ///
+    /// Defaults to the ``RIGHTHYPHENMIN`` directive of the dictionary, or 2
+    /// if there is none.
     pub fn right(&mut self, right: usize) -> &mut Self {
-        self.right = right;
+        self.right = Some(right);
+        self
+    }
+
+    /// Sets the minimum number of user-perceived characters in the first
+    /// syllable after a compound word boundary
+    ///
+    /// Defaults to the ``COMPOUNDLEFTHYPHENMIN`` directive of the dictionary,
+    /// or to the minimum of the first syllable if there is none.
+    pub fn compound_left(&mut self, compound_left: usize) -> &mut Self {
+        self.compound_left = Some(compound_left);
+        self
+    }
+
+    /// Sets the minimum number of user-perceived characters in the last
+    /// syllable before a compound word boundary
+    ///
+    /// Defaults to the ``COMPOUNDRIGHTHYPHENMIN`` directive of the
+    /// dictionary, or to the minimum of the last syllable if there is none.
+    pub fn compound_right(&mut self, compound_right: usize) -> &mut Self {
+        self.compound_right = Some(compound_right);
+        self
+    }
+
+    /// Adds patterns on top of the dictionary
+    ///
+    /// The patterns, written as in ``hyph_*.dic`` files, replace the
+    /// patterns of the dictionary with the same letters, and are combined
+    /// with the other ones as usual. Patterns added last take precedence
+    /// over the previous ones, see ``HyphDict::merge``.
+    ///
+    /// The dictionary with the additional patterns is not shared with other
+    /// hyphenation instances.
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::lang("nl_NL").patterns("g1r").build().unwrap();
+    ///
+    /// assert_eq!(dic.inserted("lettergrepen"), "let-ter-g-re-pen");
+    /// ```
+    pub fn patterns(&mut self, patterns: &str) -> &mut Self {
+        self.patterns
+            .push(PatternSource::Text(patterns.to_string()));
+        self
+    }
+
+    /// Adds the patterns of a file on top of the dictionary
+    ///
+    /// The file is read when the hyphenation instance is built, see
+    /// ``patterns``.
+    ///
+    /// - *filename* - filename of the patterns to read
+    pub fn patterns_file(&mut self, filename: &str) -> &mut Self {
+        self.patterns
+            .push(PatternSource::File(filename.to_string()));
+        self
+    }
+
+    /// Adds words hyphenated regardless of the patterns of the dictionary
+    ///
+    /// Exceptions are written with a hyphen at each allowed position, as in
+    /// TeX's ``\hyphenation``: ``ta-ble`` is only hyphenated after ``ta``,
+    /// ``table`` is never hyphenated. Words are matched regardless of their
+    /// case, and the exceptions added last replace the previous ones.
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::lang("nl_NL")
+    ///     .exceptions(vec!["letterg-repen", "lettertype"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(dic.inserted("Lettergrepen"), "Letterg-repen");
+    /// assert_eq!(dic.inserted("lettertype"), "lettertype");
+    /// ```
+    pub fn exceptions<I, S>(&mut self, exceptions: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let words = exceptions.into_iter().map(Into::into).collect();
+        self.exceptions.push(ExceptionSource::Words(words));
+        self
+    }
+
+    /// Adds the exceptions of a file
+    ///
+    /// The exceptions, written as for ``exceptions``, are separated by
+    /// whitespace, and lines starting with ``%`` or ``#`` are comments. The
+    /// file is read when the hyphenation instance is built.
+    ///
+    /// - *filename* - filename of the exceptions to read
+    pub fn exceptions_file(&mut self, filename: &str) -> &mut Self {
+        self.exceptions
+            .push(ExceptionSource::File(filename.to_string()));
         self
     }
 
     /// Sets whether to use a cached copy of the hyphenation patterns
+    ///
+    /// The cache is shared by all threads, so that each dictionary is only
+    /// loaded once per process.
     pub fn cache(&mut self, cache: bool) -> &mut Self {
         self.cache = cache;
         self
     }
+
+    /// Sets the policy of the cache of hyphenation positions
+    ///
+    /// Defaults to ``CachePolicy::Unbounded``, keeping the positions of all
+    /// the hyphenated words. The cache is shared by the clones of the
+    /// hyphenation instance.
+    pub fn cache_policy(&mut self, cache_policy: CachePolicy) -> &mut Self {
+        self.cache_policy = cache_policy;
+        self
+    }
 }
 
 impl<T> Builder<T>
@@ -97,40 +362,109 @@ where
 {
     /// Create an hyphenation instance for given lang or filename.
     ///
-    /// Returns `Err` if the given lang or filename does not exist.
-    pub fn build(&self) -> Result<Pyphen, ()> {
+    /// Returns `Err` if the given lang or filename does not exist, or if the
+    /// dictionary, additional patterns or exceptions cannot be read.
+    pub fn build(&self) -> Result<Pyphen, Error> {
         let Self {
             ref filename,
             left,
             right,
+            compound_left,
+            compound_right,
             cache,
-            mut error,
+            cache_policy,
+            embedded,
+            ref dictionary,
+            ref content,
+            ref read_error,
+            ref patterns,
+            ref exceptions,
+            ref unknown_lang,
+            ref lang,
         } = *self;
-        let filename: &str = &*filename;
-        let mut hd = None;
-
-        HD_CACHE.with(|hc| {
-            if !cache || !hc.borrow().contains_key(filename) {
-                if let Ok(hd) = HyphDict::new(filename) {
-                    hc.borrow_mut().insert(filename.to_string(), Rc::new(hd));
-                } else {
-                    error = true;
-                }
-            }
-
-            if let Some(x) = hc.borrow().get(filename) {
-                hd = Some(Rc::clone(x));
-            } else {
-                error = true;
-            }
-        });
 
-        if error {
-            Err(())
-        } else {
-            let hd = hd.unwrap();
+        if let Some(language) = unknown_lang {
+            return Err(Error::UnknownLanguage {
+                language: language.clone(),
+                tried: fallback_chain(language),
+            });
+        }
 
-            Ok(Pyphen { hd, left, right })
+        if let Some(error) = read_error {
+            return Err(Error::Io {
+                filename: MEMORY.to_string(),
+                error: io::Error::new(error.kind(), error.to_string()),
+            });
         }
+
+        let mut hd = match (dictionary, content, embedded) {
+            (Some(dictionary), _, _) => Arc::clone(dictionary),
+            (None, Some(content), _) => Arc::new(HyphDict::from_bytes(content.clone(), MEMORY)?),
+            (None, None, Some(lang)) => Source::Embedded(lang).load_shared(cache)?,
+            (None, None, None) => Source::File(filename.to_string()).load_shared(cache)?,
+        };
+        for source in patterns {
+            hd = Arc::new(source.merge(&hd)?);
+        }
+
+        let exceptions = Exceptions::new(exceptions)?;
+
+        let left = left.or(hd.metadata.left_hyphen_min).unwrap_or(2);
+        let right = right.or(hd.metadata.right_hyphen_min).unwrap_or(2);
+        let compound_left = compound_left
+            .or(hd.metadata.compound_left_hyphen_min)
+            .unwrap_or(left);
+        let compound_right = compound_right
+            .or(hd.metadata.compound_right_hyphen_min)
+            .unwrap_or(right);
+
+        Ok(Pyphen {
+            hd,
+            exceptions: Arc::new(exceptions),
+            cache: Arc::new(Cache::new(cache_policy)),
+            left,
+            right,
+            compound_left,
+            compound_right,
+            lang: lang.clone(),
+            options: Options {
+                left: self.left,
+                right: self.right,
+                compound_left: self.compound_left,
+                compound_right: self.compound_right,
+                cache,
+                cache_policy,
+            },
+        })
+    }
+}
+
+impl Pyphen {
+    /// Create an hyphenation instance for a language, with the minima, the
+    /// exceptions and the cache policy given to the builder of this instance.
+    ///
+    /// Returns ``None`` if there is no dictionary for the language.
+    ///
+    /// - *lang* - lang of the included dict to use
+    pub(super) fn with_lang(&self, lang: &str) -> Option<Pyphen> {
+        let mut builder = Builder::lang(lang);
+        let Options {
+            left,
+            right,
+            compound_left,
+            compound_right,
+            cache,
+            cache_policy,
+        } = self.options;
+        builder.left = left;
+        builder.right = right;
+        builder.compound_left = compound_left;
+        builder.compound_right = compound_right;
+        builder.cache = cache;
+        builder.cache_policy = cache_policy;
+
+        let mut dic = builder.build().ok()?;
+        dic.exceptions = Arc::clone(&self.exceptions);
+        Some(dic)
     }
 }
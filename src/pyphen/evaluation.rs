@@ -0,0 +1,142 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+use super::{grapheme_offsets, Pyphen};
+use crate::exceptions::{read_words, split_hyphens};
+use crate::Error;
+
+/// Hyphens of a word differing from its reference hyphenation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WordEvaluation {
+    /// The word, without hyphens.
+    pub word: String,
+    /// Positions found but not in the reference.
+    pub bad: Vec<usize>,
+    /// Positions of the reference not found.
+    pub missed: Vec<usize>,
+}
+
+/// Comparison of the hyphens found with reference hyphenations.
+///
+/// Positions are counted in characters, as given by ``Pyphen::positions``.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Evaluation {
+    /// Number of hyphens found and in the references.
+    pub good: usize,
+    /// Number of hyphens found but not in the references.
+    pub bad: usize,
+    /// Number of hyphens of the references not found.
+    pub missed: usize,
+    /// Words whose hyphens differ from their reference, in the order of the
+    /// references.
+    pub words: Vec<WordEvaluation>,
+}
+
+impl Evaluation {
+    /// Get the proportion of the hyphens found that are in the references.
+    ///
+    /// Returns 1 if no hyphen is found.
+    pub fn precision(&self) -> f64 {
+        match self.good + self.bad {
+            0 => 1.0,
+            found => self.good as f64 / found as f64,
+        }
+    }
+
+    /// Get the proportion of the hyphens of the references that are found.
+    ///
+    /// Returns 1 if the references have no hyphens.
+    pub fn recall(&self) -> f64 {
+        match self.good + self.missed {
+            0 => 1.0,
+            expected => self.good as f64 / expected as f64,
+        }
+    }
+}
+
+impl Pyphen {
+    /// Compare the hyphens found with reference hyphenations, such as
+    /// ``ta-ble``.
+    ///
+    /// The hyphens of the references that cannot be found with the ``left``
+    /// and ``right`` minima, or inside a user-perceived character, are
+    /// ignored.
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::lang("nl_NL").build().unwrap();
+    /// let evaluation = dic.evaluate(&["let-ter-gre-pen", "le-tter-gre-pen"]);
+    ///
+    /// assert_eq!((evaluation.good, evaluation.bad, evaluation.missed), (5, 1, 1));
+    /// assert_eq!(evaluation.words[0].word, "lettergrepen");
+    /// assert_eq!(evaluation.words[0].bad, [3]);
+    /// assert_eq!(evaluation.words[0].missed, [2]);
+    /// ```
+    pub fn evaluate<I, S>(&self, references: I) -> Evaluation
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut evaluation = Evaluation::default();
+        for reference in references {
+            let (word, hyphens) = split_hyphens(reference.as_ref());
+            if word.is_empty() {
+                continue;
+            }
+
+            let graphemes = grapheme_offsets(&word);
+            let length = graphemes.len() - 1;
+            let expected: Vec<_> = hyphens
+                .into_iter()
+                .filter(|hyphen| match graphemes.binary_search(hyphen) {
+                    Ok(g) => g >= self.left && g + self.right <= length,
+                    Err(_) => false,
+                })
+                .collect();
+            let found: Vec<_> = self.positions(&word).into_iter().map(|i| i.value).collect();
+
+            let bad: Vec<_> = found
+                .iter()
+                .filter(|i| !expected.contains(i))
+                .copied()
+                .collect();
+            let missed: Vec<_> = expected
+                .iter()
+                .filter(|i| !found.contains(i))
+                .copied()
+                .collect();
+            evaluation.good += found.len() - bad.len();
+            evaluation.bad += bad.len();
+            evaluation.missed += missed.len();
+            if !bad.is_empty() || !missed.is_empty() {
+                evaluation.words.push(WordEvaluation { word, bad, missed });
+            }
+        }
+
+        evaluation
+    }
+
+    /// Compare the hyphens found with the reference hyphenations of a file,
+    /// separated by whitespace, see ``evaluate``.
+    ///
+    /// Lines starting with ``%`` or ``#`` are comments.
+    ///
+    /// Returns `Err` if the file cannot be read.
+    pub fn evaluate_file(&self, filename: &str) -> Result<Evaluation, Error> {
+        Ok(self.evaluate(read_words(filename)?))
+    }
+}
@@ -0,0 +1,345 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use regex::Regex;
+
+use super::Pyphen;
+use crate::{fallback_chain, language_fallback};
+
+/// Elements whose content is not parsed.
+const RAW_TEXT: [&str; 3] = ["script", "style", "textarea"];
+
+/// Elements whose text is never hyphenated.
+const SKIPPED: [&str; 5] = ["script", "style", "textarea", "code", "pre"];
+
+/// Elements without content nor end tag.
+const VOID: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+lazy_static! {
+    static ref ENTITY: Regex =
+        Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z][a-zA-Z0-9]*);?").unwrap();
+    static ref HYPHENS: Regex =
+        Regex::new(r"(?i)(?:^|[;\s])(?:-[a-z]+-)?hyphens\s*:\s*([a-z-]+)").unwrap();
+}
+
+/// Get the byte ranges of the character references of a text, with the
+/// bytes around them, so that the words touching them are kept.
+pub(super) fn entities(text: &str) -> Vec<Range<usize>> {
+    ENTITY
+        .find_iter(text)
+        .map(|m| m.start().saturating_sub(1)..m.end() + 1)
+        .collect()
+}
+
+/// Open element, with what its content inherits.
+#[derive(Clone, Default)]
+struct Element {
+    name: String,
+    /// Language of the content, ``None`` if no element gives one, empty if
+    /// unknown.
+    lang: Option<String>,
+    /// Whether the content has ``translate="no"``.
+    no_translate: bool,
+    /// Whether the content has ``hyphens: none``.
+    no_hyphens: bool,
+    /// Whether the content is in a skipped element.
+    skipped: bool,
+}
+
+impl Element {
+    /// Whether the text of the content is kept as it is.
+    fn skip(&self) -> bool {
+        self.no_translate || self.no_hyphens || self.skipped
+    }
+}
+
+/// Start tag, with its attributes.
+struct Tag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    self_closing: bool,
+}
+
+impl Tag {
+    /// Get the value of an attribute.
+    fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse a start tag at the beginning of the string, after its ``<``.
+///
+/// Returns the tag and the length of the string it takes, including its
+/// closing ``>``.
+fn parse_tag(html: &str) -> (Tag, usize) {
+    let bytes = html.as_bytes();
+    let mut index = 0;
+    let take = |index: &mut usize, stop: &dyn Fn(u8) -> bool| {
+        let start = *index;
+        while *index < bytes.len() && !stop(bytes[*index]) {
+            *index += 1;
+        }
+        html[start..*index].to_string()
+    };
+    let space = |byte: u8| byte.is_ascii_whitespace();
+
+    let name = take(&mut index, &|byte| {
+        space(byte) || byte == b'>' || byte == b'/'
+    });
+    let mut tag = Tag {
+        name: name.to_ascii_lowercase(),
+        attributes: Vec::new(),
+        self_closing: false,
+    };
+    loop {
+        take(&mut index, &|byte| !space(byte));
+        match bytes.get(index) {
+            None => return (tag, index),
+            Some(b'>') => return (tag, index + 1),
+            Some(b'/') => {
+                index += 1;
+                if bytes.get(index) == Some(&b'>') {
+                    tag.self_closing = true;
+                    return (tag, index + 1);
+                }
+                continue;
+            }
+            Some(_) => (),
+        }
+
+        let name = take(&mut index, &|byte| {
+            space(byte) || byte == b'=' || byte == b'>' || byte == b'/'
+        });
+        take(&mut index, &|byte| !space(byte));
+        let mut value = String::new();
+        if bytes.get(index) == Some(&b'=') {
+            index += 1;
+            take(&mut index, &|byte| !space(byte));
+            value = match bytes.get(index) {
+                Some(&quote) if quote == b'"' || quote == b'\'' => {
+                    index += 1;
+                    let value = take(&mut index, &|byte| byte == quote);
+                    index = (index + 1).min(bytes.len());
+                    value
+                }
+                _ => take(&mut index, &|byte| space(byte) || byte == b'>'),
+            };
+        }
+        tag.attributes.push((name.to_ascii_lowercase(), value));
+    }
+}
+
+/// Hyphenator of the text nodes of a document.
+struct Document<'a> {
+    dic: &'a Pyphen,
+    /// Languages using ``dic``, its own language and the more general ones.
+    own: Vec<String>,
+    hyphen: &'a str,
+    /// Languages found, with their fallback, ``None`` if unavailable.
+    languages: HashMap<String, Option<String>>,
+    /// Dictionaries of the fallback languages other than the language of
+    /// ``dic``, ``None`` if unavailable.
+    dictionaries: HashMap<String, Option<Pyphen>>,
+    stack: Vec<Element>,
+    output: String,
+}
+
+impl<'a> Document<'a> {
+    /// Write the text of a text node, hyphenated unless skipped.
+    fn text(&mut self, text: &str) {
+        let element = self.stack.last().cloned().unwrap_or_default();
+        let own = self.dic;
+        let dic = match element.lang {
+            _ if element.skip() => None,
+            None => Some(own),
+            Some(lang) => match self
+                .languages
+                .entry(lang)
+                .or_insert_with_key(|lang| language_fallback(lang))
+            {
+                None => None,
+                Some(lang) if self.own.contains(lang) => Some(own),
+                Some(lang) => self
+                    .dictionaries
+                    .entry(lang.clone())
+                    .or_insert_with(|| own.with_lang(lang))
+                    .as_ref(),
+            },
+        };
+        let dic = match dic {
+            Some(dic) => dic,
+            None => return self.output.push_str(text),
+        };
+
+        let text = dic.inserted_text_except(text, self.hyphen, &entities(text));
+        self.output.push_str(&text);
+    }
+
+    /// Open an element.
+    fn open(&mut self, tag: &Tag) {
+        let mut element = self.stack.last().cloned().unwrap_or_default();
+        element.name = tag.name.clone();
+
+        // xml:lang has priority over lang
+        if let Some(lang) = tag.get("xml:lang").or_else(|| tag.get("lang")) {
+            element.lang = Some(lang.trim().to_string());
+        }
+        match tag.get("translate").map(str::trim) {
+            Some(value) if value.eq_ignore_ascii_case("no") => element.no_translate = true,
+            Some(value) if value.eq_ignore_ascii_case("yes") || value.is_empty() => {
+                element.no_translate = false
+            }
+            _ => (),
+        }
+        let style = tag.get("style").unwrap_or_default();
+        if let Some(hyphens) = HYPHENS.captures_iter(style).last() {
+            element.no_hyphens = hyphens[1].eq_ignore_ascii_case("none");
+        }
+        element.skipped |= SKIPPED.contains(&tag.name.as_str());
+
+        self.stack.push(element);
+    }
+
+    /// Close the last open element with the given name.
+    fn close(&mut self, name: &str) {
+        if let Some(index) = self.stack.iter().rposition(|element| element.name == name) {
+            self.stack.truncate(index);
+        }
+    }
+
+    /// Hyphenate a document.
+    fn hyphenate(mut self, html: &str) -> String {
+        let mut index = 0;
+        while index < html.len() {
+            let rest = &html[index..];
+            let markup = match rest.find('<') {
+                Some(start) => start,
+                None => {
+                    self.text(rest);
+                    break;
+                }
+            };
+            self.text(&rest[..markup]);
+            index += markup;
+            let rest = &html[index..];
+
+            // length of the markup, copied as it is
+            let length = if let Some(comment) = rest.strip_prefix("<!--") {
+                comment.find("-->").map_or(rest.len(), |end| end + 7)
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                rest.find('>').map_or(rest.len(), |end| end + 1)
+            } else if rest.starts_with("</")
+                && rest[2..].starts_with(|c: char| c.is_ascii_alphabetic())
+            {
+                let (tag, length) = parse_tag(&rest[2..]);
+                self.close(&tag.name);
+                length + 2
+            } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                let (tag, length) = parse_tag(&rest[1..]);
+                let mut length = length + 1;
+                if RAW_TEXT.contains(&tag.name.as_str()) {
+                    // copy the content until the end tag
+                    let content = &rest[length..];
+                    let end = format!("</{}", tag.name);
+                    let lowercase = content.to_ascii_lowercase();
+                    length += lowercase.find(&end).unwrap_or(content.len());
+                } else if !tag.self_closing && !VOID.contains(&tag.name.as_str()) {
+                    self.open(&tag);
+                }
+                length
+            } else {
+                // a lone < is text
+                self.text("<");
+                index += 1;
+                continue;
+            };
+            self.output.push_str(&rest[..length]);
+            index += length;
+        }
+
+        self.output
+    }
+}
+
+impl Pyphen {
+    /// Get the HTML document with all the possible hyphens inserted in the
+    /// words of its text.
+    ///
+    /// Only the text nodes are hyphenated: tags, attributes, comments and
+    /// character references are kept as they are, and so are the words
+    /// touching character references. The text of ``script``, ``style``,
+    /// ``textarea``, ``code`` and ``pre`` elements, and of elements with a
+    /// ``hyphens: none`` style or a ``translate="no"`` attribute, is not
+    /// hyphenated.
+    ///
+    /// The dictionary of the text is given by the ``lang`` or ``xml:lang``
+    /// attribute of its elements, with ``language_fallback``. Text without
+    /// language, or in the language of this instance or a more general one,
+    /// uses this instance. Text in other languages uses their dictionary,
+    /// with the minima, exceptions and cache policy given to the builder of
+    /// this instance, and text in a language without dictionary is not
+    /// hyphenated.
+    ///
+    /// - *html* - unicode string of the HTML document or fragment
+    /// - *hyphen* - unicode string used as hyphen character
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::lang("en_US").build().unwrap();
+    /// let html = dic.inserted_html_with(
+    ///     "<p title=\"hyphenation\">Hyphenation, <span lang=\"nl\">lettergrepen</span> \
+    ///      <code>hyphenation</code></p>",
+    ///     "&shy;",
+    /// );
+    ///
+    /// assert_eq!(
+    ///     html,
+    ///     "<p title=\"hyphenation\">Hy&shy;phen&shy;ation, <span lang=\"nl\">let&shy;ter&shy;gre&shy;pen</span> \
+    ///      <code>hyphenation</code></p>"
+    /// );
+    /// ```
+    pub fn inserted_html_with(&self, html: &str, hyphen: &str) -> String {
+        let document = Document {
+            dic: self,
+            own: self.lang.as_deref().map(fallback_chain).unwrap_or_default(),
+            hyphen,
+            languages: HashMap::new(),
+            dictionaries: HashMap::new(),
+            stack: Vec::new(),
+            output: String::with_capacity(html.len()),
+        };
+
+        document.hyphenate(html)
+    }
+
+    /// Get the HTML document with soft hyphens (U+00AD) inserted in the
+    /// words of its text, see ``inserted_html_with``.
+    ///
+    /// - *html* - unicode string of the HTML document or fragment
+    pub fn inserted_html(&self, html: &str) -> String {
+        self.inserted_html_with(html, "\u{ad}")
+    }
+}
@@ -14,8 +14,9 @@
 // details.
 
 use std::borrow::Cow;
+use std::ops::Range;
 
-use crate::DataInt;
+use crate::{char_offsets, DataInt};
 
 /// Iterator over all hyphenation possibilities
 pub struct Iter<'a> {
@@ -29,34 +30,61 @@ impl<'a> Iterator for Iter<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let position = self.iter.next()?;
+        let (first, second, _) = split(self.word, &position, self.is_upper);
+        Some((first, second))
+    }
+}
+
+/// Get the range of characters replaced by the nonstandard hyphenation of a
+/// position, with the change.
+///
+/// Returns ``None`` for standard positions, and for alternatives that do not
+/// fit the word.
+///
+/// - *length* - number of characters of the word
+pub(super) fn replaced(position: &DataInt, length: usize) -> Option<(&str, Range<usize>)> {
+    let (change, index, cut) = position.data.as_ref()?;
+    let index = index + position.value as isize;
+    let start = if index < 0 {
+        length.checked_sub(index.unsigned_abs())?
+    } else {
+        index as usize
+    };
+    let end = start.checked_add(*cut).filter(|&end| end <= length)?;
+
+    Some((change, start..end))
+}
+
+/// Split a word at a hyphenation position.
+///
+/// Returns the two parts, and the byte range of the word replaced by the
+/// nonstandard hyphenation, empty for standard ones.
+pub(super) fn split<'a>(
+    word: &'a str,
+    position: &DataInt,
+    is_upper: bool,
+) -> (Cow<'a, str>, Cow<'a, str>, Range<usize>) {
+    let offsets = char_offsets(word);
 
-        if let Some(data) = position.data {
-            // get the nonstandard hyphenation data
-            let (change, mut index, cut) = data;
-            let change = if self.is_upper {
-                change.to_uppercase()
-            } else {
-                change.to_string()
-            };
-            index += position.value as isize;
-            let (c1, c2) = {
-                let mut x = change.split('=');
-                (x.next().unwrap(), x.next().unwrap())
-            };
-
-            let index = if index < 0 {
-                self.word.len() - index as usize
-            } else {
-                index as usize
-            };
-
-            let first = self.word[..index].to_string() + c1;
-            let second = c2.to_string() + &self.word[(index + cut)..];
-            Some((Cow::Owned(first), Cow::Owned(second)))
+    if let Some((change, range)) = replaced(position, offsets.len() - 1) {
+        // get the nonstandard hyphenation data
+        let change = if is_upper {
+            change.to_uppercase()
         } else {
-            let first = &self.word[..position.value];
-            let second = &self.word[position.value..];
-            Some((Cow::Borrowed(first), Cow::Borrowed(second)))
-        }
+            change.to_string()
+        };
+        let (c1, c2) = {
+            let mut x = change.split('=');
+            (x.next().unwrap(), x.next().unwrap())
+        };
+
+        let replaced = offsets[range.start]..offsets[range.end];
+        let first = word[..replaced.start].to_string() + c1;
+        let second = c2.to_string() + &word[replaced.end..];
+        (Cow::Owned(first), Cow::Owned(second), replaced)
+    } else {
+        let offset = offsets[position.value];
+        let (first, second) = word.split_at(offset);
+        (Cow::Borrowed(first), Cow::Borrowed(second), offset..offset)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,527 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::paragraph::{tokens, Break, Line};
+use super::Pyphen;
+
+/// Badness of the lines that cannot be adjusted.
+const INFINITE_BADNESS: f64 = 10000.0;
+
+/// Penalty forbidding a break.
+const INFINITE_PENALTY: f64 = 10000.0;
+
+/// Parameters of the total-fit line breaking of Knuth and Plass.
+///
+/// Lengths are counted in user-perceived characters. Spaces are one
+/// character wide, and can stretch or shrink to justify the lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KnuthPlass {
+    hyphen: String,
+    hyphen_penalty: f64,
+    double_hyphen_demerits: f64,
+    fitness_demerits: f64,
+    line_penalty: f64,
+    tolerance: f64,
+    looseness: isize,
+    space_stretch: f64,
+    space_shrink: f64,
+}
+
+impl Default for KnuthPlass {
+    fn default() -> Self {
+        Self {
+            hyphen: "-".to_string(),
+            hyphen_penalty: 50.0,
+            double_hyphen_demerits: 10000.0,
+            fitness_demerits: 10000.0,
+            line_penalty: 10.0,
+            tolerance: 2.0,
+            looseness: 0,
+            space_stretch: 0.5,
+            space_shrink: 1.0 / 3.0,
+        }
+    }
+}
+
+impl KnuthPlass {
+    /// Constructs the default parameters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the string used as hyphen character
+    ///
+    /// Defaults to ``-``.
+    pub fn hyphen(&mut self, hyphen: &str) -> &mut Self {
+        self.hyphen = hyphen.to_string();
+        self
+    }
+
+    /// Sets the penalty of the lines ending with a hyphenated word
+    ///
+    /// Words are not hyphenated if the penalty is 10000 or more. Defaults to
+    /// 50, as ``\hyphenpenalty`` in TeX.
+    pub fn hyphen_penalty(&mut self, hyphen_penalty: f64) -> &mut Self {
+        self.hyphen_penalty = hyphen_penalty;
+        self
+    }
+
+    /// Sets the demerits added for two consecutive lines ending with a
+    /// hyphenated word
+    ///
+    /// Defaults to 10000, as ``\doublehyphendemerits`` in TeX.
+    pub fn double_hyphen_demerits(&mut self, double_hyphen_demerits: f64) -> &mut Self {
+        self.double_hyphen_demerits = double_hyphen_demerits;
+        self
+    }
+
+    /// Sets the demerits added for two consecutive lines whose spaces are
+    /// adjusted very differently
+    ///
+    /// Defaults to 10000, as ``\adjdemerits`` in TeX.
+    pub fn fitness_demerits(&mut self, fitness_demerits: f64) -> &mut Self {
+        self.fitness_demerits = fitness_demerits;
+        self
+    }
+
+    /// Sets the penalty of each line, favouring fewer lines
+    ///
+    /// Defaults to 10, as ``\linepenalty`` in TeX.
+    pub fn line_penalty(&mut self, line_penalty: f64) -> &mut Self {
+        self.line_penalty = line_penalty;
+        self
+    }
+
+    /// Sets the maximum adjustment ratio of the lines
+    ///
+    /// If the paragraph cannot be broken within this tolerance, lines as
+    /// loose as needed are accepted, and overfull lines if some words are
+    /// longer than the width. Defaults to 2.
+    pub fn tolerance(&mut self, tolerance: f64) -> &mut Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the number of lines to add to, or to remove from, the optimal
+    /// breaking, as far as possible within the tolerance
+    ///
+    /// Defaults to 0, as ``\looseness`` in TeX.
+    pub fn looseness(&mut self, looseness: isize) -> &mut Self {
+        self.looseness = looseness;
+        self
+    }
+
+    /// Sets how much the spaces can stretch, in characters
+    ///
+    /// Defaults to 0.5.
+    pub fn space_stretch(&mut self, space_stretch: f64) -> &mut Self {
+        self.space_stretch = space_stretch;
+        self
+    }
+
+    /// Sets how much the spaces can shrink, in characters
+    ///
+    /// Defaults to 1/3.
+    pub fn space_shrink(&mut self, space_shrink: f64) -> &mut Self {
+        self.space_shrink = space_shrink;
+        self
+    }
+}
+
+/// Line ending at a breakpoint chosen by the total-fit line breaking.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakpoint {
+    /// The line, as given by ``Pyphen::wrap_paragraph_lines``.
+    pub line: Line,
+    /// Adjustment ratio of the spaces of the line: the part of their
+    /// stretchability used if positive, of their shrinkability if negative.
+    ///
+    /// Overfull lines have a ratio lower than -1, lines that cannot stretch
+    /// enough have an infinite ratio, and the last line has a ratio of 0
+    /// unless it is shrunk.
+    pub ratio: f64,
+}
+
+/// Element of a paragraph.
+enum Item {
+    /// Text between breakpoints, with its width.
+    Box(Range<usize>, f64),
+    /// Space between words.
+    Glue,
+    /// Hyphenation point.
+    Hyphen {
+        /// Text ending the line if broken, with its width.
+        before: (String, f64),
+        /// Text starting the next line if broken, with its width.
+        after: (String, f64),
+        /// Range of the text replaced if broken, with its width.
+        replaced: (Range<usize>, f64),
+    },
+    /// End of the paragraph.
+    End,
+}
+
+/// Feasible breakpoint.
+struct Node {
+    /// Index of the item where the line is broken, ``None`` for the start of
+    /// the paragraph.
+    item: Option<usize>,
+    /// Number of lines before the breakpoint.
+    line: usize,
+    /// Fitness class of the line before the breakpoint, from tight to very
+    /// loose.
+    fitness: usize,
+    /// Adjustment ratio of the line before the breakpoint.
+    ratio: f64,
+    /// Demerits of the paragraph until the breakpoint.
+    demerits: f64,
+    /// Totals of the widths, stretchabilities and shrinkabilities of the
+    /// items until the breakpoint, included.
+    totals: (f64, f64, f64),
+    /// Width of the text starting the next line.
+    after: f64,
+    /// Whether the line before the breakpoint ends with a hyphen.
+    hyphenated: bool,
+    /// Index of the node of the previous breakpoint.
+    previous: usize,
+}
+
+/// Get the width of a string, in user-perceived characters.
+fn width(string: &str) -> f64 {
+    string.graphemes(true).count() as f64
+}
+
+impl Pyphen {
+    /// Get the elements of a paragraph.
+    fn items(&self, text: &str, hyphen: &str) -> Vec<Item> {
+        let boxed = |range: Range<usize>| Item::Box(range.clone(), width(&text[range]));
+        let mut items = Vec::new();
+        for (offset, token) in tokens(text) {
+            if !items.is_empty() {
+                items.push(Item::Glue);
+            }
+            let mut start = offset;
+            for Break {
+                replaced,
+                before,
+                after,
+            } in self.breaks(token, offset)
+            {
+                let before = before + hyphen;
+                items.push(boxed(start..replaced.start));
+                items.push(Item::Hyphen {
+                    before: (before.clone(), width(&before)),
+                    after: (after.clone(), width(&after)),
+                    replaced: (replaced.clone(), width(&text[replaced.clone()])),
+                });
+                start = replaced.end;
+            }
+            items.push(boxed(start..offset + token.len()));
+        }
+        items.push(Item::End);
+
+        items
+    }
+
+    /// Break a paragraph into justified lines, minimizing the demerits of the
+    /// whole paragraph as described by Knuth and Plass.
+    ///
+    /// Lines can be broken between words, and at the hyphenation points
+    /// given by ``positions``, with a penalty. Nonstandard hyphenations
+    /// change the text around the hyphen as by ``iterate``. Words are
+    /// separated by a single space, whatever the whitespace of the
+    /// paragraph.
+    ///
+    /// - *text* - unicode string of the paragraph
+    /// - *width* - length of the lines, in user-perceived characters
+    /// - *options* - parameters of the line breaking
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::{Builder, KnuthPlass};
+    ///
+    /// let dic = Builder::lang("en_US").build().unwrap();
+    /// let text = "Hyphenation is the process of breaking words between lines to \
+    ///             create more consistency across a text block.";
+    /// let breakpoints = dic.break_paragraph(text, 28, KnuthPlass::new().hyphen_penalty(100.0));
+    ///
+    /// let lines: Vec<_> = breakpoints.iter().map(|b| b.line.text.as_str()).collect();
+    /// assert_eq!(
+    ///     lines,
+    ///     [
+    ///         "Hyphenation is the process of",
+    ///         "breaking words between lines",
+    ///         "to create more consistency",
+    ///         "across a text block.",
+    ///     ]
+    /// );
+    /// assert!(breakpoints.iter().all(|b| b.ratio.abs() <= 2.0));
+    /// ```
+    pub fn break_paragraph(
+        &self,
+        text: &str,
+        width: usize,
+        options: &KnuthPlass,
+    ) -> Vec<Breakpoint> {
+        let items = self.items(text, &options.hyphen);
+        if items.len() == 1 {
+            return Vec::new();
+        }
+
+        // accept any line if needed, as TeX does in its last pass
+        let width = width as f64;
+        let nodes = self
+            .breakpoints(&items, width, options, options.tolerance)
+            .or_else(|| self.breakpoints(&items, width, options, f64::INFINITY))
+            .unwrap();
+
+        // choose the breakpoints at the end of the paragraph with the best
+        // demerits, and the closest number of lines to the looseness
+        let ends: Vec<_> = (0..nodes.len())
+            .filter(|&index| nodes[index].item == Some(items.len() - 1))
+            .collect();
+        let best = ends
+            .iter()
+            .map(|&index| &nodes[index])
+            .min_by(|a, b| a.demerits.total_cmp(&b.demerits))
+            .unwrap();
+        let lines = best.line as isize + options.looseness;
+        let mut index = ends
+            .into_iter()
+            .min_by(|&a, &b| {
+                let (a, b) = (&nodes[a], &nodes[b]);
+                let distance = |node: &Node| (node.line as isize - lines).abs();
+                distance(a)
+                    .cmp(&distance(b))
+                    .then(a.demerits.total_cmp(&b.demerits))
+            })
+            .unwrap();
+
+        let mut breakpoints = Vec::new();
+        while let Some(end) = nodes[index].item {
+            let node = &nodes[index];
+            let start = nodes[node.previous].item.map_or(0, |item| item + 1);
+            let mut line = Line {
+                text: String::new(),
+                range: match &items[start] {
+                    Item::Box(range, _) => range.start..range.start,
+                    _ => unreachable!(),
+                },
+                hyphenated: node.hyphenated,
+            };
+            if let Some(Item::Hyphen { after, .. }) = start.checked_sub(1).map(|i| &items[i]) {
+                line.text.push_str(&after.0);
+            }
+            for (item_index, item) in items.iter().enumerate().take(end + 1).skip(start) {
+                let broken = item_index == end;
+                match item {
+                    Item::Box(range, _) => {
+                        line.text.push_str(&text[range.clone()]);
+                        line.range.end = range.end;
+                    }
+                    Item::Glue if !broken => line.text.push(' '),
+                    Item::Hyphen {
+                        before, replaced, ..
+                    } => {
+                        match broken {
+                            true => line.text.push_str(&before.0),
+                            false => line.text.push_str(&text[replaced.0.clone()]),
+                        }
+                        line.range.end = replaced.0.end;
+                    }
+                    Item::Glue | Item::End => (),
+                }
+            }
+
+            breakpoints.push(Breakpoint {
+                line,
+                ratio: node.ratio,
+            });
+            index = node.previous;
+        }
+        breakpoints.reverse();
+
+        breakpoints
+    }
+
+    /// Get the feasible breakpoints of a paragraph.
+    ///
+    /// Returns ``None`` if the lines cannot be adjusted within the
+    /// tolerance. If the tolerance is infinite, overfull lines are accepted
+    /// when no breakpoint is feasible.
+    fn breakpoints(
+        &self,
+        items: &[Item],
+        width: f64,
+        options: &KnuthPlass,
+        tolerance: f64,
+    ) -> Option<Vec<Node>> {
+        let mut nodes = vec![Node {
+            item: None,
+            line: 0,
+            fitness: 1,
+            ratio: 0.0,
+            demerits: 0.0,
+            totals: (0.0, 0.0, 0.0),
+            after: 0.0,
+            hyphenated: false,
+            previous: 0,
+        }];
+        let mut active = vec![0];
+
+        // totals of the widths, stretchabilities and shrinkabilities of the
+        // items before the current one
+        let mut totals = (0.0, 0.0, 0.0);
+        for (item_index, item) in items.iter().enumerate() {
+            let (penalty, before, next) = match item {
+                Item::Box(_, w) => {
+                    totals.0 += w;
+                    continue;
+                }
+                Item::Glue => (
+                    0.0,
+                    0.0,
+                    (
+                        totals.0 + 1.0,
+                        totals.1 + options.space_stretch,
+                        totals.2 + options.space_shrink,
+                    ),
+                ),
+                Item::Hyphen { replaced, .. } if options.hyphen_penalty >= INFINITE_PENALTY => {
+                    totals.0 += replaced.1;
+                    continue;
+                }
+                Item::Hyphen {
+                    before, replaced, ..
+                } => (
+                    options.hyphen_penalty,
+                    before.1,
+                    (totals.0 + replaced.1, totals.1, totals.2),
+                ),
+                Item::End => (f64::NEG_INFINITY, 0.0, totals),
+            };
+            let end = matches!(item, Item::End);
+            let hyphenated = matches!(item, Item::Hyphen { .. });
+
+            // best breakpoint for each fitness class, and for each number of
+            // lines if the looseness is not 0
+            let mut candidates = BTreeMap::new();
+            let mut overfull: Option<(usize, f64, f64)> = None;
+            active.retain(|&index| {
+                let node = &nodes[index];
+                let line_width = totals.0 - node.totals.0 + node.after + before;
+                let stretch = match end {
+                    true => f64::INFINITY,
+                    false => totals.1 - node.totals.1,
+                };
+                let shrink = totals.2 - node.totals.2;
+                let ratio = if line_width < width {
+                    match stretch > 0.0 {
+                        true => (width - line_width) / stretch,
+                        false => f64::INFINITY,
+                    }
+                } else if line_width > width {
+                    match shrink > 0.0 {
+                        true => (width - line_width) / shrink,
+                        false => f64::NEG_INFINITY,
+                    }
+                } else {
+                    0.0
+                };
+
+                if (-1.0..=tolerance).contains(&ratio) {
+                    let badness = (100.0 * ratio.abs().powi(3)).min(INFINITE_BADNESS);
+                    let mut demerits = (options.line_penalty + badness).powi(2);
+                    if penalty >= 0.0 {
+                        demerits += penalty.powi(2);
+                    } else if penalty.is_finite() {
+                        demerits -= penalty.powi(2);
+                    }
+                    if hyphenated && node.hyphenated {
+                        demerits += options.double_hyphen_demerits;
+                    }
+                    let fitness: usize = match ratio {
+                        ratio if ratio < -0.5 => 0,
+                        ratio if ratio <= 0.5 => 1,
+                        ratio if ratio <= 1.0 => 2,
+                        _ => 3,
+                    };
+                    if fitness.abs_diff(node.fitness) > 1 {
+                        demerits += options.fitness_demerits;
+                    }
+                    demerits += node.demerits;
+
+                    let line = match options.looseness {
+                        0 => 0,
+                        _ => node.line + 1,
+                    };
+                    let best =
+                        candidates
+                            .entry((line, fitness))
+                            .or_insert((f64::INFINITY, index, ratio));
+                    if demerits < best.0 {
+                        *best = (demerits, index, ratio);
+                    }
+                } else if ratio < -1.0
+                    && overfull
+                        .iter()
+                        .all(|&(_, _, excess)| line_width - width < excess)
+                {
+                    overfull = Some((index, ratio, line_width - width));
+                }
+
+                ratio >= -1.0 && !end
+            });
+
+            if candidates.is_empty() && active.is_empty() {
+                // break the least overfull line if nothing else is possible
+                let (index, ratio, _) = overfull.filter(|_| tolerance.is_infinite())?;
+                candidates.insert(
+                    (0, 0),
+                    (
+                        nodes[index].demerits + INFINITE_BADNESS.powi(2),
+                        index,
+                        ratio,
+                    ),
+                );
+            }
+            for ((_, fitness), (demerits, previous, ratio)) in candidates {
+                active.push(nodes.len());
+                nodes.push(Node {
+                    item: Some(item_index),
+                    line: nodes[previous].line + 1,
+                    fitness,
+                    ratio,
+                    demerits,
+                    totals: next,
+                    after: match item {
+                        Item::Hyphen { after, .. } => after.1,
+                        _ => 0.0,
+                    },
+                    hyphenated,
+                    previous,
+                });
+            }
+            totals = next;
+        }
+
+        Some(nodes)
+    }
+}
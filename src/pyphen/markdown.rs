@@ -0,0 +1,382 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+use std::ops::Range;
+
+use regex::Regex;
+
+use super::html::entities;
+use super::Pyphen;
+
+/// HTML elements whose block ends with their end tag.
+const RAW_HTML: [&str; 4] = ["pre", "script", "style", "textarea"];
+
+/// HTML elements starting a block, even in a paragraph.
+const BLOCK_HTML: [&str; 36] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "center",
+    "details",
+    "dialog",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "section",
+    "summary",
+    "table",
+    "tr",
+    "ul",
+];
+
+lazy_static! {
+    static ref CONTAINER: Regex = Regex::new(r"^(?: {0,3}> ?)*").unwrap();
+    static ref DEFINITION: Regex = Regex::new(r"(?m)^(?: {0,3}> ?)* {0,3}\[([^\]]+)\]:").unwrap();
+    static ref HTML_BLOCK: Regex =
+        Regex::new(r"^<(?:(!--)|(\?)|(!)|(/)?([a-zA-Z][a-zA-Z0-9-]*)(?:[\s/>]|$))").unwrap();
+    static ref HTML_TAG: Regex = Regex::new(r"^</?[a-zA-Z][^<>]*>$").unwrap();
+    static ref URL: Regex = Regex::new(
+        r"(?i)\b(?:(?:https?|ftp)://|www\.|mailto:)[^\s<>]*[^\s<>.,:;!?'`)\]]|[\w.+-]+@[\w-]+(?:\.[\w-]+)+"
+    )
+    .unwrap();
+}
+
+/// Block kept as it is, with what closes it.
+enum Block {
+    /// Front matter, closed by one of the given lines.
+    FrontMatter(&'static [&'static str]),
+    /// Fenced code, closed by at least the given number of the given fence
+    /// character.
+    Fence(char, usize),
+    /// HTML block, closed by a line containing the given string, or by a
+    /// blank line.
+    Html(Option<String>),
+}
+
+impl Block {
+    /// Get the fenced code opened by a line, without its indentation.
+    fn fence(line: &str) -> Option<Self> {
+        let fence = line.chars().next().filter(|&c| c == '`' || c == '~')?;
+        let length = line.len() - line.trim_start_matches(fence).len();
+        let info = &line[length..];
+        if length < 3 || (fence == '`' && info.contains('`')) {
+            return None;
+        }
+        Some(Block::Fence(fence, length))
+    }
+
+    /// Get the HTML block opened by a line, without its indentation.
+    ///
+    /// Lines with only a tag open a block unless they are in a paragraph.
+    fn html(line: &str, paragraph: bool) -> Option<Self> {
+        let captures = HTML_BLOCK.captures(line)?;
+        let end = if captures.get(1).is_some() {
+            "-->".to_string()
+        } else if captures.get(2).is_some() {
+            "?>".to_string()
+        } else if captures.get(3).is_some() {
+            ">".to_string()
+        } else {
+            let name = captures[5].to_ascii_lowercase();
+            if captures.get(4).is_none() && RAW_HTML.contains(&name.as_str()) {
+                format!("</{}>", name)
+            } else if BLOCK_HTML.contains(&name.as_str())
+                || (!paragraph && HTML_TAG.is_match(line.trim_end()))
+            {
+                return Some(Block::Html(None));
+            } else {
+                return None;
+            }
+        };
+        Some(Block::Html(Some(end)))
+    }
+
+    /// Whether a line closes the block, the opening line included.
+    fn closed(&self, line: &str, opening: bool) -> bool {
+        match self {
+            Block::FrontMatter(ends) => !opening && ends.contains(&line.trim_end()),
+            Block::Fence(fence, length) => {
+                let line = CONTAINER.find(line).map_or(line, |m| &line[m.end()..]);
+                let code = line.trim_start_matches(' ');
+                let rest = code.trim_start_matches(*fence);
+                !opening
+                    && line.len() - code.len() < 4
+                    && code.len() - rest.len() >= *length
+                    && rest.trim().is_empty()
+            }
+            Block::Html(Some(end)) => {
+                let line = line.to_ascii_lowercase();
+                // the end of a comment is not its start
+                let start = if opening && end == "-->" { 4 } else { 0 };
+                line.get(start..)
+                    .is_some_and(|line| line.contains(end.as_str()))
+            }
+            Block::Html(None) => !opening && line.trim().is_empty(),
+        }
+    }
+}
+
+/// Normalize a link label, as labels are matched.
+fn label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Get the byte ranges of the inline code, URLs, link targets and HTML of a
+/// paragraph.
+///
+/// - *markdown* - the whole document
+/// - *paragraph* - the byte range of the paragraph in the document
+/// - *labels* - the normalized labels of the link reference definitions
+fn inline(markdown: &str, paragraph: Range<usize>, labels: &[String]) -> Vec<Range<usize>> {
+    let text = &markdown[paragraph.clone()];
+    let bytes = text.as_bytes();
+    let mut kept = entities(text);
+    kept.extend(URL.find_iter(text).map(|m| m.range()));
+
+    // only ASCII bytes are matched, so that slices are at char boundaries
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = bytes[index];
+        let next = bytes.get(index + 1).copied();
+        let end = match byte {
+            b'\\' => {
+                index += 2;
+                continue;
+            }
+            b'`' => {
+                // code span, closed by the same number of backticks
+                let length = text[index..].len() - text[index..].trim_start_matches('`').len();
+                let mut end = None;
+                let mut close = index + length;
+                while let Some(start) = text[close..].find('`').map(|start| close + start) {
+                    let run = text[start..].len() - text[start..].trim_start_matches('`').len();
+                    if run == length {
+                        end = Some(start + run);
+                        break;
+                    }
+                    close = start + run;
+                }
+                match end {
+                    Some(end) => end,
+                    None => {
+                        index += length;
+                        continue;
+                    }
+                }
+            }
+            b'<' if next.is_some_and(|b| b.is_ascii_alphabetic() || b"/!?".contains(&b)) => {
+                // autolink or inline HTML
+                match text[index + 1..].find(['<', '>']) {
+                    Some(end) if bytes[index + 1 + end] == b'>' => index + end + 2,
+                    _ => index + 1,
+                }
+            }
+            b']' if next == Some(b'(') => {
+                // link target, with balanced parentheses
+                let mut depth = 0;
+                let end = text[index + 1..].find(|c| {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => (),
+                    }
+                    depth == 0
+                });
+                kept.extend(end.map(|end| index + 1..index + end + 2));
+                index += 1;
+                continue;
+            }
+            b']' if next == Some(b'[') => {
+                // reference link label
+                kept.extend(
+                    text[index + 1..]
+                        .find(']')
+                        .map(|end| index + 1..index + end + 2),
+                );
+                index += 1;
+                continue;
+            }
+            b'[' => {
+                // the text of shortcut and collapsed reference links is
+                // their label
+                match text[index + 1..].find(['[', ']']) {
+                    Some(end) if bytes[index + 1 + end] == b']' => {
+                        let after = &text[index + end + 2..];
+                        let shortcut = !after.starts_with(['(', '[']) || after.starts_with("[]");
+                        match shortcut && labels.contains(&label(&text[index + 1..index + 1 + end]))
+                        {
+                            true => index + end + 2,
+                            false => index + 1,
+                        }
+                    }
+                    _ => index + 1,
+                }
+            }
+            _ => index + 1,
+        };
+        if end > index + 1 {
+            kept.push(index..end);
+        }
+        index = end;
+    }
+
+    kept.iter()
+        .map(|range| paragraph.start + range.start..paragraph.start + range.end)
+        .collect()
+}
+
+/// Get the sorted and disjoint byte ranges of a Markdown document kept as
+/// they are.
+fn kept(markdown: &str) -> Vec<Range<usize>> {
+    let labels: Vec<_> = DEFINITION
+        .captures_iter(markdown)
+        .map(|captures| label(&captures[1]))
+        .collect();
+
+    let mut kept = Vec::new();
+    let mut block: Option<Block> = None;
+    let mut paragraph: Option<usize> = None;
+    let mut offset = 0;
+    for (number, line) in markdown.split_inclusive('\n').enumerate() {
+        let range = offset..offset + line.len();
+        offset = range.end;
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        if let Some(current) = &block {
+            kept.push(range);
+            if current.closed(line, false) {
+                block = None;
+            }
+            continue;
+        }
+
+        let inner = CONTAINER.find(line).map_or(line, |m| &line[m.end()..]);
+        let content = inner.trim_start_matches([' ', '\t']);
+        let indent = inner[..inner.len() - content.len()]
+            .chars()
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum::<usize>();
+        let opened = if number == 0 && line == "---" {
+            Some(Block::FrontMatter(&["---", "..."]))
+        } else if number == 0 && line == "+++" {
+            Some(Block::FrontMatter(&["+++"]))
+        } else if indent < 4 {
+            Block::fence(content).or_else(|| Block::html(content, paragraph.is_some()))
+        } else {
+            None
+        };
+
+        let code = indent >= 4 && paragraph.is_none();
+        let definition = paragraph.is_none() && DEFINITION.is_match(line);
+        if content.is_empty() || opened.is_some() || code || definition {
+            if let Some(start) = paragraph.take() {
+                kept.extend(inline(markdown, start..range.start, &labels));
+            }
+            if let Some(opened) = opened {
+                if !opened.closed(line, true) {
+                    block = Some(opened);
+                }
+                kept.push(range);
+            } else if code || definition {
+                kept.push(range);
+            }
+        } else {
+            paragraph.get_or_insert(range.start);
+        }
+    }
+    if let Some(start) = paragraph {
+        kept.extend(inline(markdown, start..markdown.len(), &labels));
+    }
+
+    // merge the overlapping ranges
+    kept.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(kept.len());
+    for range in kept {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+impl Pyphen {
+    /// Get the Markdown document with all the possible hyphens inserted in
+    /// the words of its text.
+    ///
+    /// Inline code, fenced and indented code blocks, URLs, link targets and
+    /// reference labels, HTML blocks and tags, character references and
+    /// front matter are kept as they are, and so are the words touching
+    /// character references.
+    ///
+    /// - *markdown* - unicode string of the Markdown document
+    /// - *hyphen* - unicode string used as hyphen character
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::lang("nl_NL").build().unwrap();
+    /// let markdown = dic.inserted_markdown_with(
+    ///     "Meer [lettergrepen](https://nl.wikipedia.org/wiki/lettergrepen) \
+    ///      en `lettergrepen`.\n\n```\nlettergrepen\n```\n",
+    ///     "-",
+    /// );
+    ///
+    /// assert_eq!(
+    ///     markdown,
+    ///     "Meer [let-ter-gre-pen](https://nl.wikipedia.org/wiki/lettergrepen) \
+    ///      en `lettergrepen`.\n\n```\nlettergrepen\n```\n"
+    /// );
+    /// ```
+    pub fn inserted_markdown_with(&self, markdown: &str, hyphen: &str) -> String {
+        self.inserted_text_except(markdown, hyphen, &kept(markdown))
+    }
+
+    /// Get the Markdown document with soft hyphens (U+00AD) inserted in the
+    /// words of its text, see ``inserted_markdown_with``.
+    ///
+    /// - *markdown* - unicode string of the Markdown document
+    pub fn inserted_markdown(&self, markdown: &str) -> String {
+        self.inserted_markdown_with(markdown, "\u{ad}")
+    }
+}
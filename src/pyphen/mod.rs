@@ -14,33 +14,186 @@
 // details.
 
 use std::borrow::Cow;
-use std::rc::Rc;
+use std::sync::Arc;
 
-use super::{DataInt, HyphDict};
-use iter::Iter;
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Cache, CachePolicy, CacheStats, DataInt, Exceptions, HyphDict, Metadata};
+use builder::Options;
+use iter::{replaced, Iter};
 
 pub mod builder;
+pub mod evaluation;
+pub mod html;
 pub mod iter;
+pub mod knuth_plass;
+pub mod markdown;
+pub mod paragraph;
+pub mod text;
 
 /// Hyphenation class, with methods to hyphenate strings in various ways.
+///
+/// Instances can be cloned and shared between threads, clones use the same
+/// dictionary and cache.
+#[derive(Clone)]
 pub struct Pyphen {
     left: usize,
     right: usize,
-    hd: Rc<HyphDict>,
+    compound_left: usize,
+    compound_right: usize,
+    hd: Arc<HyphDict>,
+    exceptions: Arc<Exceptions>,
+    cache: Arc<Cache>,
+    /// Language of the dictionary, if built with ``Builder::lang``.
+    lang: Option<String>,
+    options: Options,
 }
 
 impl Pyphen {
+    /// Get the minimum number of user-perceived characters in the first
+    /// syllable.
+    pub fn left(&self) -> usize {
+        self.left
+    }
+
+    /// Get the minimum number of user-perceived characters in the last
+    /// syllable.
+    pub fn right(&self) -> usize {
+        self.right
+    }
+
+    /// Get the minimum number of user-perceived characters in the first
+    /// syllable after a compound word boundary.
+    pub fn compound_left(&self) -> usize {
+        self.compound_left
+    }
+
+    /// Get the minimum number of user-perceived characters in the last
+    /// syllable before a compound word boundary.
+    pub fn compound_right(&self) -> usize {
+        self.compound_right
+    }
+
+    /// Get the header directives read from the dictionary.
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::lang("en_US").build().unwrap();
+    ///
+    /// assert_eq!(dic.metadata().right_hyphen_min, Some(3));
+    /// ```
+    pub fn metadata(&self) -> &Metadata {
+        &self.hd.metadata
+    }
+
+    /// Get the statistics of the cache of hyphenation positions.
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::{Builder, CachePolicy};
+    ///
+    /// let dic = Builder::lang("nl_NL")
+    ///     .cache_policy(CachePolicy::MaxEntries(1000))
+    ///     .build()
+    ///     .unwrap();
+    /// dic.inserted("lettergrepen");
+    /// dic.inserted("lettergrepen");
+    ///
+    /// let stats = dic.cache_stats();
+    /// assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    /// ```
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Remove all the hyphenation positions from the cache.
+    ///
+    /// The statistics of hits, misses and evictions are kept.
+    pub fn clear_cache(&self) {
+        self.cache.clear()
+    }
+
     /// Get a list of positions where the word can be hyphenated.
-    /// The points that are too far to the left or right are removed.
+    /// The positions of the exceptions given to the builder replace the
+    /// positions allowed by the dictionary.
+    /// The points that are too far to the left or right are removed, and so
+    /// are the points too close to a compound word boundary or next to a
+    /// ``NOHYPHEN`` string of the dictionary.
+    ///
+    /// Positions are counted in characters, use ``DataInt::byte_offset`` to
+    /// get byte offsets. Distances to the ends of the word and to compound
+    /// word boundaries are counted in user-perceived characters, and points
+    /// inside a user-perceived character are removed.
+    ///
+    /// Compound word boundaries have their ``compound`` attribute set.
     ///
     /// - *word* - unicode string of the word to hyphenate
     pub fn positions(&self, word: &str) -> Vec<DataInt> {
-        let right = word.len() - self.right;
-        self.hd
-            .positions(word)
+        let graphemes = grapheme_offsets(word);
+        let length = graphemes.len() - 1;
+        let grapheme = |point: &DataInt| graphemes.binary_search(&point.value);
+        let chars = word.chars().count();
+
+        let points = self.points(word);
+        let no_hyphen = self.hd.no_hyphen_positions(word);
+        points
             .iter()
-            .cloned()
-            .filter(|i| i.value >= self.left && i.value <= right)
+            .enumerate()
+            .filter(|&(index, i)| {
+                let g = match grapheme(i) {
+                    Ok(g) => g,
+                    Err(_) => return false,
+                };
+                if g < self.left || g + self.right > length || no_hyphen.contains(&i.value) {
+                    return false;
+                }
+                if i.data.is_some() && replaced(i, chars).is_none() {
+                    return false;
+                }
+                if i.compound {
+                    return true;
+                }
+
+                // check the distance to the surrounding compound boundaries
+                let previous = points[..index].iter().rev().find(|p| p.compound);
+                let next = points[index + 1..].iter().find(|p| p.compound);
+                let distance = |p| grapheme(p).unwrap_or_else(|g| g);
+                previous
+                    .iter()
+                    .all(|&p| g - distance(p) >= self.compound_left)
+                    && next.iter().all(|&p| distance(p) - g >= self.compound_right)
+            })
+            .map(|(_, i)| i.clone())
+            .collect()
+    }
+
+    /// Get the positions allowed by the exceptions or by the dictionary,
+    /// from the cache if possible.
+    fn points(&self, word: &str) -> Arc<Vec<DataInt>> {
+        if let Some(points) = self.exceptions.get(word) {
+            return points;
+        }
+        if let Some(points) = self.cache.get(word) {
+            return points;
+        }
+
+        let points = Arc::new(self.hd.positions(word));
+        self.cache.insert(word.to_string(), Arc::clone(&points));
+
+        points
+    }
+
+    /// Get a list of the compound word boundaries where the word can be
+    /// hyphenated.
+    ///
+    /// - *word* - unicode string of the word to hyphenate
+    pub fn compound_positions(&self, word: &str) -> Vec<usize> {
+        self.positions(word)
+            .into_iter()
+            .filter(|i| i.compound)
+            .map(|i| i.value)
             .collect()
     }
 
@@ -63,17 +216,18 @@ impl Pyphen {
     /// if the word could not be hyphenated.
     ///
     /// - *word* - unicode string of the word to hyphenate
-    /// - *width* - maximum length of the first part
+    /// - *width* - maximum length of the first part, in user-perceived
+    ///   characters
     /// - *hyphen* - unicode string used as hyphen character
     pub fn wrap_with<'b>(
         &self,
         word: &'b str,
-        mut width: usize,
+        width: usize,
         hyphen: &str,
     ) -> Option<(String, Cow<'b, str>)> {
-        width -= hyphen.len();
+        let width = width.checked_sub(hyphen.graphemes(true).count())?;
         for (w1, w2) in self.iterate(word) {
-            if w1.len() <= width {
+            if w1.graphemes(true).count() <= width {
                 let w1 = w1.into_owned();
                 return Some((w1 + hyphen, w2));
             }
@@ -90,7 +244,8 @@ impl Pyphen {
     /// if the word could not be hyphenated.
     ///
     /// - *word* - unicode string of the word to hyphenate
-    /// - *width* - maximum length of the first part
+    /// - *width* - maximum length of the first part, in user-perceived
+    ///   characters
     pub fn wrap<'b>(&self, word: &'b str, width: usize) -> Option<(String, Cow<'b, str>)> {
         self.wrap_with(word, width, "-")
     }
@@ -112,24 +267,18 @@ impl Pyphen {
         let mut word_list: Vec<_> = word.chars().collect();
         let is_upper = word == word.to_uppercase();
 
+        let length = word_list.len();
+
         for position in self.positions(word).into_iter().rev() {
-            if let Some(data) = position.data {
+            if let Some((change, range)) = replaced(&position, length) {
                 // get the nonstandard hyphenation data
-                let (change, mut index, cut) = data;
                 let change = if is_upper {
                     change.to_uppercase()
                 } else {
                     change.to_string()
                 };
-                index += position.value as isize;
 
-                let index = if index < 0 {
-                    word_list.len() - index as usize
-                } else {
-                    index as usize
-                };
-
-                word_list.splice(index..(index + cut), change.replace('=', hyphen).chars());
+                word_list.splice(range, change.replace('=', hyphen).chars());
             } else {
                 word_list.splice(position.value..position.value, hyphen.chars());
             }
@@ -154,3 +303,14 @@ impl Pyphen {
         self.inserted_with(word, "-")
     }
 }
+
+/// Get the character offsets of the user-perceived characters of a word,
+/// including the offset of its end.
+fn grapheme_offsets(word: &str) -> Vec<usize> {
+    let mut graphemes = vec![0];
+    for grapheme in word.graphemes(true) {
+        graphemes.push(graphemes[graphemes.len() - 1] + grapheme.chars().count());
+    }
+
+    graphemes
+}
@@ -0,0 +1,227 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::iter::split;
+use super::text::words;
+use super::Pyphen;
+
+/// Line of a wrapped paragraph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    /// Text of the line, with the hyphen if its last word is hyphenated.
+    pub text: String,
+    /// Byte range of the line in the paragraph.
+    ///
+    /// The characters changed by a nonstandard hyphenation are in the range
+    /// of the line ending with the hyphen.
+    pub range: Range<usize>,
+    /// Whether the last word of the line is hyphenated.
+    pub hyphenated: bool,
+}
+
+/// Hyphenation point of a whitespace-separated part of a paragraph.
+pub(super) struct Break {
+    /// Byte range of the paragraph replaced by the hyphenation, empty for
+    /// standard ones.
+    pub(super) replaced: Range<usize>,
+    /// Text replacing the range before the hyphen.
+    pub(super) before: String,
+    /// Text replacing the range at the beginning of the next line.
+    pub(super) after: String,
+}
+
+/// Get the whitespace-separated parts of a paragraph, with their byte
+/// offsets.
+pub(super) fn tokens(text: &str) -> impl Iterator<Item = (usize, &str)> + '_ {
+    text.split(char::is_whitespace)
+        .filter(|token| !token.is_empty())
+        .map(move |token| (token.as_ptr() as usize - text.as_ptr() as usize, token))
+}
+
+impl Pyphen {
+    /// Get the hyphenation points of a whitespace-separated part of a
+    /// paragraph, in order.
+    ///
+    /// Points whose replaced text overlaps the one of a previous point are
+    /// removed.
+    ///
+    /// - *offset* - byte offset of the part in the paragraph
+    pub(super) fn breaks(&self, token: &str, offset: usize) -> Vec<Break> {
+        let mut breaks = Vec::new();
+        let mut start = offset;
+        for (word_offset, word) in words(token) {
+            let base = offset + word_offset;
+            let is_upper = word == word.to_uppercase();
+            for position in self.positions(word) {
+                let (first, second, replaced) = split(word, &position, is_upper);
+                let before = first[replaced.start..].to_string();
+                let after = second[..second.len() - (word.len() - replaced.end)].to_string();
+                let replaced = base + replaced.start..base + replaced.end;
+                if replaced.start < start {
+                    continue;
+                }
+                start = replaced.end;
+                breaks.push(Break {
+                    replaced,
+                    before,
+                    after,
+                });
+            }
+        }
+
+        breaks
+    }
+
+    /// Wrap a paragraph into lines, hyphenating words if needed.
+    ///
+    /// Lines are filled greedily: each line gets as many words as possible,
+    /// and the first word that does not fit is hyphenated as by ``iterate``,
+    /// with the longest first part fitting. Words are separated by a single
+    /// space, whatever the whitespace of the paragraph. Words longer than
+    /// the width and that cannot be hyphenated have their own line.
+    ///
+    /// - *text* - unicode string of the paragraph
+    /// - *width* - maximum length of the lines, in user-perceived characters
+    /// - *hyphen* - unicode string used as hyphen character
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::{Builder, Line};
+    ///
+    /// let dic = Builder::lang("hu_HU").left(1).right(1).build().unwrap();
+    /// let lines = dic.wrap_paragraph_lines("a  kulissza ott", 9, "-");
+    ///
+    /// assert_eq!(lines[0], Line { text: "a kulisz-".into(), range: 0..8, hyphenated: true });
+    /// assert_eq!(lines[1], Line { text: "sza ott".into(), range: 8..15, hyphenated: false });
+    /// ```
+    pub fn wrap_paragraph_lines(&self, text: &str, width: usize, hyphen: &str) -> Vec<Line> {
+        let mut lines = Vec::new();
+        let mut line: Option<Line> = None;
+        let mut line_width = 0;
+        for (offset, token) in tokens(text) {
+            let end = offset + token.len();
+            let breaks = self.breaks(token, offset);
+
+            // the rest of the token starts with the text after the last
+            // hyphen, followed by the text of the paragraph from start, and
+            // can be broken at the following points
+            let mut after = String::new();
+            let mut start = offset;
+            let mut next = 0;
+            loop {
+                let space = usize::from(line.is_some());
+                let rest = format!("{}{}", after, &text[start..end]);
+                let rest_width = rest.graphemes(true).count();
+                let available = width.saturating_sub(line_width + space);
+                let fits = rest_width <= available;
+                let first = |point: &Break| {
+                    let source = &text[start..point.replaced.start];
+                    format!("{}{}{}{}", after, source, point.before, hyphen)
+                };
+                let split = match fits {
+                    true => None,
+                    false => breaks[next..]
+                        .iter()
+                        .enumerate()
+                        .rev()
+                        .map(|(index, point)| (next + index, point, first(point)))
+                        .find(|(_, _, first)| first.graphemes(true).count() <= available),
+                };
+
+                // the rest of the token has its own line if it is too long
+                if fits || (split.is_none() && line.is_none()) {
+                    match &mut line {
+                        Some(line) => {
+                            line.text.push(' ');
+                            line.text.push_str(&rest);
+                            line.range.end = end;
+                        }
+                        None => {
+                            line = Some(Line {
+                                text: rest,
+                                range: start..end,
+                                hyphenated: false,
+                            })
+                        }
+                    }
+                    line_width += space + rest_width;
+                    break;
+                }
+
+                match split {
+                    Some((index, point, first)) => {
+                        let mut current = line.take().unwrap_or(Line {
+                            text: String::new(),
+                            range: start..start,
+                            hyphenated: false,
+                        });
+                        if space == 1 {
+                            current.text.push(' ');
+                        }
+                        current.text.push_str(&first);
+                        current.range.end = point.replaced.end;
+                        current.hyphenated = true;
+                        lines.push(current);
+
+                        after = point.after.clone();
+                        start = point.replaced.end;
+                        next = index + 1;
+                    }
+                    None => lines.extend(line.take()),
+                }
+                line_width = 0;
+            }
+        }
+        lines.extend(line);
+
+        lines
+    }
+
+    /// Wrap a paragraph into lines, hyphenating words if needed, see
+    /// ``wrap_paragraph_lines``.
+    ///
+    /// - *text* - unicode string of the paragraph
+    /// - *width* - maximum length of the lines, in user-perceived characters
+    /// - *hyphen* - unicode string used as hyphen character
+    pub fn wrap_paragraph_with(&self, text: &str, width: usize, hyphen: &str) -> Vec<String> {
+        self.wrap_paragraph_lines(text, width, hyphen)
+            .into_iter()
+            .map(|line| line.text)
+            .collect()
+    }
+
+    /// Wrap a paragraph into lines, hyphenating words if needed, see
+    /// ``wrap_paragraph_lines``.
+    ///
+    /// - *text* - unicode string of the paragraph
+    /// - *width* - maximum length of the lines, in user-perceived characters
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::lang("nl_NL").build().unwrap();
+    /// let lines = dic.wrap_paragraph("Het aantal lettergrepen van een woord.", 15);
+    ///
+    /// assert_eq!(lines, ["Het aantal let-", "tergrepen van", "een woord."]);
+    /// ```
+    pub fn wrap_paragraph(&self, text: &str, width: usize) -> Vec<String> {
+        self.wrap_paragraph_with(text, width, "-")
+    }
+}
@@ -0,0 +1,137 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::Pyphen;
+
+/// Get the words of a text, with their byte offsets in the text.
+///
+/// The text is split at the word boundaries of Unicode (UAX #29), and the
+/// punctuation around each word is removed. Parts of the text without
+/// letters, such as numbers, are not words.
+///
+/// # Example
+/// ```
+/// let words: Vec<_> = pyphen_rs::words("«Lettergrepen», 2 l'été.").collect();
+///
+/// assert_eq!(words, [(2, "Lettergrepen"), (20, "l'été")]);
+/// ```
+pub fn words(text: &str) -> impl Iterator<Item = (usize, &str)> + '_ {
+    text.split_word_bound_indices()
+        .filter_map(|(offset, segment)| {
+            // strip the user-perceived characters that are not letters or
+            // digits, keeping the combining marks of the letters
+            let is_letter =
+                |grapheme: &str| grapheme.chars().next().is_some_and(char::is_alphanumeric);
+            let graphemes: Vec<_> = segment.grapheme_indices(true).collect();
+            let first = graphemes.iter().position(|(_, g)| is_letter(g))?;
+            let last = graphemes.iter().rposition(|(_, g)| is_letter(g))?;
+            let start = graphemes[first].0;
+            let end = graphemes[last].0 + graphemes[last].1.len();
+
+            let word = &segment[start..end];
+            Some((offset + start, word)).filter(|_| word.chars().any(char::is_alphabetic))
+        })
+}
+
+impl Pyphen {
+    /// Get the byte offsets in a text where its words can be hyphenated.
+    ///
+    /// Words are found as by ``words``. The offsets of nonstandard
+    /// hyphenations are given too, ``inserted_text_with`` applies their
+    /// changes.
+    ///
+    /// - *text* - unicode string of the text to hyphenate
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::lang("nl_NL").build().unwrap();
+    ///
+    /// assert_eq!(dic.text_positions("(lettergrepen)"), [4, 7, 10]);
+    /// ```
+    pub fn text_positions(&self, text: &str) -> Vec<usize> {
+        words(text)
+            .flat_map(|(offset, word)| {
+                self.positions(word)
+                    .into_iter()
+                    .map(move |position| offset + position.byte_offset(word))
+            })
+            .collect()
+    }
+
+    /// Get the text with all the possible hyphens inserted in its words.
+    ///
+    /// Words are found as by ``words``, the rest of the text is kept as it
+    /// is.
+    ///
+    /// - *text* - unicode string of the text to hyphenate
+    /// - *hyphen* - unicode string used as hyphen character
+    ///
+    /// # Example
+    /// ```
+    /// use pyphen_rs::Builder;
+    ///
+    /// let dic = Builder::lang("nl_NL").build().unwrap();
+    /// let text = dic.inserted_text_with("Twee (lettergrepen),\n  en meer!", "·");
+    ///
+    /// assert_eq!(text, "Twee (let·ter·gre·pen),\n  en meer!");
+    /// ```
+    pub fn inserted_text_with(&self, text: &str, hyphen: &str) -> String {
+        self.inserted_text_except(text, hyphen, &[])
+    }
+
+    /// Get the text with all the possible hyphens inserted in its words,
+    /// except in the words overlapping the given byte ranges.
+    ///
+    /// - *text* - unicode string of the text to hyphenate
+    /// - *hyphen* - unicode string used as hyphen character
+    /// - *kept* - byte ranges of the text kept as they are, sorted by their
+    ///   start and by their end
+    pub(super) fn inserted_text_except(
+        &self,
+        text: &str,
+        hyphen: &str,
+        kept: &[Range<usize>],
+    ) -> String {
+        let mut inserted = String::with_capacity(text.len());
+        let mut kept = kept.iter().peekable();
+        let mut last = 0;
+        for (offset, word) in words(text) {
+            let end = offset + word.len();
+            while kept.next_if(|range| range.end <= offset).is_some() {}
+            if kept.peek().is_some_and(|range| range.start < end) {
+                continue;
+            }
+            inserted.push_str(&text[last..offset]);
+            inserted.push_str(&self.inserted_with(word, hyphen));
+            last = end;
+        }
+        inserted.push_str(&text[last..]);
+
+        inserted
+    }
+
+    /// Get the text with all the possible hyphens inserted in its words.
+    ///
+    /// - *text* - unicode string of the text to hyphenate
+    pub fn inserted_text(&self, text: &str) -> String {
+        self.inserted_text_with(text, "-")
+    }
+}
@@ -0,0 +1,242 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! TeX hyphenation patterns, as distributed by the hyph-utf8 project.
+//!
+//! ``hyph-*.tex`` files hold their patterns in ``\patterns{...}`` and their
+//! exceptions in ``\hyphenation{...}``, with a header of comments giving
+//! their metadata:
+//!
+//! ```text
+//! % hyphenmins:
+//! %     typesetting:
+//! %         left: 2
+//! %         right: 3
+//! ```
+//!
+//! ``hyph-*.pat.txt`` and ``hyph-*.hyp.txt`` files only hold the patterns
+//! and the exceptions, separated by whitespace.
+
+use std::io::BufRead;
+
+use super::{Error, Metadata};
+
+/// Content of a TeX hyphenation file.
+#[derive(Default)]
+pub struct Tex {
+    /// The minima of the ``hyphenmins`` header.
+    pub metadata: Metadata,
+    /// Patterns, with their line numbers.
+    pub patterns: Vec<(usize, String)>,
+    /// Exceptions, in the ``ta-ble`` notation.
+    pub exceptions: Vec<String>,
+}
+
+/// Group of a TeX command whose content is read.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Group {
+    Patterns,
+    Hyphenation,
+}
+
+/// State of the reader of a TeX file.
+#[derive(Default)]
+struct Reader {
+    tex: Tex,
+    /// Whether a ``\patterns`` command has been found.
+    commands: bool,
+    /// Tokens outside of any group, patterns of plain files.
+    loose: Vec<(usize, String)>,
+    /// Group of the last command, until its opening brace.
+    command: Option<Group>,
+    /// Group being read and its depth.
+    group: Option<(Group, usize)>,
+    /// Depth of the other groups.
+    depth: usize,
+    /// First line of patterns or exceptions with a command, not supported.
+    malformed: Option<(usize, String)>,
+    /// Indentation of the ``hyphenmins`` header, while it is read.
+    hyphenmins: Option<usize>,
+    /// Subsection of the ``hyphenmins`` header being read.
+    section: String,
+    /// Minima of the ``generation`` subsection, used if there is no
+    /// ``typesetting`` subsection.
+    generation: (Option<usize>, Option<usize>),
+}
+
+impl Reader {
+    /// Store a token read at the given line.
+    fn token(&mut self, line: usize, token: &mut String) {
+        if token.is_empty() {
+            return;
+        }
+
+        let token = std::mem::take(token);
+        match self.group {
+            Some((Group::Patterns, _)) => self.tex.patterns.push((line, token)),
+            Some((Group::Hyphenation, _)) => self.tex.exceptions.push(token),
+            None if self.depth == 0 && !token.starts_with('\\') => self.loose.push((line, token)),
+            None => (),
+        }
+    }
+
+    /// Read the text of a line, without its comment.
+    fn text(&mut self, line: usize, text: &str) {
+        let mut token = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    self.token(line, &mut token);
+                    if self.group.is_some() && self.malformed.is_none() {
+                        self.malformed = Some((line, text.trim().to_string()));
+                    }
+                    let mut name = String::new();
+                    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                        name.push(c);
+                        chars.next();
+                    }
+                    self.command = match name.as_str() {
+                        "patterns" => Some(Group::Patterns),
+                        "hyphenation" => Some(Group::Hyphenation),
+                        _ => None,
+                    };
+                    self.commands |= self.command == Some(Group::Patterns);
+                    if name.is_empty() {
+                        // escaped character, such as \%
+                        chars.next();
+                    }
+                }
+                '{' => {
+                    self.token(line, &mut token);
+                    match (&mut self.group, self.command.take()) {
+                        (Some((_, depth)), _) => *depth += 1,
+                        (None, Some(group)) if self.depth == 0 => self.group = Some((group, 1)),
+                        (None, _) => self.depth += 1,
+                    }
+                }
+                '}' => {
+                    self.token(line, &mut token);
+                    match &mut self.group {
+                        Some((_, 1)) => self.group = None,
+                        Some((_, depth)) => *depth -= 1,
+                        None => self.depth = self.depth.saturating_sub(1),
+                    }
+                }
+                c if c.is_whitespace() => self.token(line, &mut token),
+                c => token.push(c),
+            }
+        }
+        self.token(line, &mut token);
+    }
+
+    /// Read a comment, that can be a line of the ``hyphenmins`` header.
+    fn comment(&mut self, comment: &str) {
+        let indent = comment.len() - comment.trim_start().len();
+        let comment = comment.trim();
+        if comment == "hyphenmins:" {
+            self.hyphenmins = Some(indent);
+            return;
+        }
+        match self.hyphenmins {
+            Some(hyphenmins) if indent > hyphenmins && !comment.is_empty() => (),
+            _ => {
+                self.hyphenmins = None;
+                return;
+            }
+        }
+
+        let (key, value) = match comment.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => return,
+        };
+        if value.is_empty() {
+            self.section = key.to_string();
+            return;
+        }
+        let value = value.parse().ok();
+        let minima = if self.section == "generation" {
+            (&mut self.generation.0, &mut self.generation.1)
+        } else {
+            let metadata = &mut self.tex.metadata;
+            (
+                &mut metadata.left_hyphen_min,
+                &mut metadata.right_hyphen_min,
+            )
+        };
+        match key {
+            "left" => *minima.0 = value,
+            "right" => *minima.1 = value,
+            _ => (),
+        }
+    }
+}
+
+/// Read the patterns, the exceptions and the metadata of a TeX file.
+///
+/// The patterns of files without ``\patterns`` commands, such as
+/// ``.pat.txt`` files, are the tokens separated by whitespace.
+///
+/// Returns `Err` if the file cannot be read, is not valid UTF-8, or has TeX
+/// commands in its patterns or exceptions.
+pub fn read<R: BufRead>(stream: R, filename: &str) -> Result<Tex, Error> {
+    let mut reader = Reader::default();
+    for (line, text) in stream.lines().enumerate() {
+        let text = text.map_err(|error| match error.kind() {
+            std::io::ErrorKind::InvalidData => Error::InvalidEncoding {
+                filename: filename.to_string(),
+                line: line + 1,
+            },
+            _ => Error::Io {
+                filename: filename.to_string(),
+                error,
+            },
+        })?;
+
+        // comments start with % unless it is escaped
+        let comment = text
+            .char_indices()
+            .find(|&(index, c)| c == '%' && !text[..index].ends_with('\\'))
+            .map(|(index, _)| index);
+        match comment {
+            Some(index) => {
+                reader.comment(&text[index + 1..]);
+                reader.text(line + 1, &text[..index]);
+            }
+            None => {
+                reader.hyphenmins = None;
+                reader.text(line + 1, &text);
+            }
+        }
+    }
+
+    if let Some((line, pattern)) = reader.malformed {
+        return Err(Error::MalformedPattern {
+            filename: filename.to_string(),
+            line,
+            pattern,
+        });
+    }
+
+    let mut tex = reader.tex;
+    if !reader.commands {
+        tex.patterns = reader.loose;
+    }
+    let metadata = &mut tex.metadata;
+    metadata.left_hyphen_min = metadata.left_hyphen_min.or(reader.generation.0);
+    metadata.right_hyphen_min = metadata.right_hyphen_min.or(reader.generation.1);
+
+    Ok(tex)
+}
@@ -0,0 +1,127 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! Command-line interface.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Run the command with the given arguments and standard input.
+fn pyphen(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pyphen"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+/// Get the standard output of a successful command.
+fn stdout(args: &[&str], input: &str) -> String {
+    let output = pyphen(args, input);
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Test words given as arguments.
+#[test]
+fn test_words() {
+    assert_eq!(
+        stdout(&["-l", "nl_NL", "lettergrepen"], ""),
+        "let-ter-gre-pen\n"
+    );
+    assert_eq!(
+        stdout(
+            &[
+                "--lang=nl_NL",
+                "--hyphen",
+                "·",
+                "lettergrepen",
+                "dictionaire"
+            ],
+            ""
+        ),
+        "let·ter·gre·pen\ndic·ti·o·nai·re\n"
+    );
+    assert_eq!(
+        stdout(
+            &["-l", "nl_NL", "--left", "4", "--right=4", "lettergrepen"],
+            ""
+        ),
+        "letter-grepen\n"
+    );
+    assert_eq!(
+        stdout(&["-l", "nl_NL", "-p", "lettergrepen"], ""),
+        "lettergrepen\t3 6 9\n"
+    );
+    assert_eq!(
+        stdout(&["-l", "nl_NL", "-i", "lettergrepen"], ""),
+        "lettergre-\tpen\nletter-\tgrepen\nlet-\ttergrepen\n"
+    );
+}
+
+/// Test text read from the standard input.
+#[test]
+fn test_text() {
+    let text = "Hello, wonderful world!\n\n  Hyphenation\tfor all.";
+    assert_eq!(
+        stdout(&[], text),
+        "Hello, won-der-ful world!\n\n  Hy-phen-ation\tfor all."
+    );
+    assert_eq!(
+        stdout(&["-p", "-"], text),
+        "Hello\t\nwonderful\t3 6\nworld\t\nHyphenation\t2 6\nfor\t\nall\t\n"
+    );
+}
+
+/// Test the list of languages and custom dictionaries.
+#[test]
+fn test_dictionaries() {
+    let languages = stdout(&["--list"], "");
+    assert!(languages.lines().any(|lang| lang == "nl_NL"));
+
+    let filename = std::env::temp_dir().join("pyphen_rs_cli.dic");
+    std::fs::write(&filename, "UTF-8\n1b\n").unwrap();
+    let output = stdout(&["-d", filename.to_str().unwrap(), "ababab"], "");
+    std::fs::remove_file(&filename).unwrap();
+    assert_eq!(output, "aba-bab\n");
+}
+
+/// Test the errors.
+#[test]
+fn test_errors() {
+    for args in [
+        &["--unknown"][..],
+        &["--left"],
+        &["--left", "two", "word"],
+        &["-l", "xx", "word"],
+        &["-d", "missing.dic", "word"],
+        &["-f", "missing.txt"],
+    ] {
+        let output = pyphen(args, "");
+        assert!(!output.status.success(), "{:?}", args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with("pyphen: "), "{}", stderr);
+    }
+}
@@ -0,0 +1,237 @@
+// This file is part of pyphen-rs
+//
+// Copyright 2008 - Wilbert Berendsen <info@wilbertberendsen.nl>
+// Copyright 2012-2013 - Guillaume Ayoub <guillaume.ayoub@kozea.fr>
+// Copyright 2019 - Naresh Ganduri <gandurinaresh@gmail.com>
+//
+// This library is free software.  It is released under the
+// GPL 2.0+/LGPL 2.1+/MPL 1.1 tri-license.  See COPYING.GPL, COPYING.LGPL and
+// COPYING.MPL for more details.
+//
+// This library is distributed in the hope that it will be useful, but WITHOUT
+// ANY WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+// FOR A PARTICULAR PURPOSE.  See the GNU General Public License for more
+// details.
+
+//! Hyphenation of non-ASCII words with the included dictionaries.
+
+use std::fs;
+use std::sync::Arc;
+
+use pyphen_rs::{validate, Builder, HyphDict, LintKind, LANGUAGES};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Get the languages of ``LANGUAGES`` with their dictionary files, sorted by
+/// language.
+fn dictionaries() -> Vec<(String, String)> {
+    let mut dictionaries: Vec<_> = LANGUAGES
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(lang, filename)| (lang.clone(), filename.to_string()))
+        .collect();
+    dictionaries.sort();
+
+    dictionaries
+}
+
+/// Get some words made of the letters of the patterns of a dictionary.
+fn sample_words(content: &str) -> Vec<String> {
+    let letters: Vec<String> = content
+        .lines()
+        .filter(|line| !line.starts_with('%') && !line.starts_with('#'))
+        .filter(|line| !line.contains(char::is_whitespace))
+        .map(|line| {
+            line.split('/')
+                .next()
+                .unwrap()
+                .chars()
+                .filter(|c| !c.is_ascii_digit() && *c != '.')
+                .collect()
+        })
+        .filter(|letters: &String| !letters.is_empty())
+        .collect();
+    let step = (letters.len() / 200).max(1);
+
+    letters
+        .windows(2)
+        .step_by(step)
+        .map(|pair| pair.concat())
+        .collect()
+}
+
+/// Test words of languages with non-ASCII letters.
+#[test]
+fn test_words() {
+    let words = [
+        ("bg_BG", "Здравейте", "Здра-вей-те"),
+        ("ca", "col·lecció", "col-lec-ci-ó"),
+        ("cs_CZ", "čtvrtletí", "čtvrt-le-tí"),
+        ("de_DE", "Straßenbahn", "Stra-ßen-bahn"),
+        ("de_DE", "Übergrößenträger", "Über-grö-ßen-trä-ger"),
+        ("el_GR", "ΘΑΛΑΣΣΑ", "ΘΑ-ΛΑΣ-ΣΑ"),
+        ("es", "España", "Es-pa-ña"),
+        ("et_EE", "jäätmekäitlus", "jäät-me-käit-lus"),
+        ("hu_HU", "Összefoglaló", "Ösz-sze-fog-la-ló"),
+        ("is", "Íslendingar", "Ís-lend-ing-ar"),
+        ("lt_LT", "lietuviškas", "lie-tu-viš-kas"),
+        ("lv_LV", "latviešu", "lat-vie-šu"),
+        ("nb_NO", "blåbærsyltetøy", "blå-bær-syl-te-tøy"),
+        ("pl_PL", "przedsiębiorstwo", "przed-się-bior-stwo"),
+        ("pt_BR", "informação", "in-for-ma-ção"),
+        (
+            "ru_RU",
+            "Достопримечательность",
+            "До-сто-при-ме-ча-тель-ность",
+        ),
+        ("sk_SK", "päťdesiat", "päť-de-siat"),
+        ("sl_SI", "čebelarstvo", "če-be-lar-stvo"),
+        ("sr", "Београд", "Бео-град"),
+        ("sv", "fågelsång", "få-gel-sång"),
+        ("te_IN", "హైదరాబాదు", "హైద-రా-బాదు"),
+    ];
+
+    for (lang, word, inserted) in words.iter() {
+        let dic = Builder::lang(lang).build().unwrap();
+        assert_eq!(dic.inserted(word), *inserted, "{}", lang);
+    }
+}
+
+/// Test that positions are counted in characters.
+#[test]
+fn test_char_positions() {
+    let dic = Builder::lang("ru_RU").build().unwrap();
+    let word = "Достопримечательность";
+    let positions = dic.positions(word);
+    let values: Vec<_> = positions.iter().map(|i| i.value).collect();
+    let offsets: Vec<_> = positions.iter().map(|i| i.byte_offset(word)).collect();
+    assert_eq!(values, vec![2, 5, 8, 10, 12, 16]);
+    assert_eq!(offsets, vec![4, 10, 16, 20, 24, 32]);
+
+    let mut iter = dic.iterate(word);
+    let (first, second) = iter.next().unwrap();
+    assert_eq!((&*first, &*second), ("Достопримечатель", "ность"));
+
+    let (first, second) = dic.wrap(word, 10).unwrap();
+    assert_eq!((&*first, &*second), ("Достопри-", "мечательность"));
+}
+
+/// Test that ``left`` and ``right`` count user-perceived characters.
+#[test]
+fn test_grapheme_left_right() {
+    let dic = Builder::lang("te_IN").build().unwrap();
+    assert_eq!(dic.inserted("హైదరాబాదు"), "హైద-రా-బాదు");
+    let dic = Builder::lang("te_IN").left(3).build().unwrap();
+    assert_eq!(dic.inserted("హైదరాబాదు"), "హైదరా-బాదు");
+    let dic = Builder::lang("te_IN").right(3).build().unwrap();
+    assert_eq!(dic.inserted("హైదరాబాదు"), "హైద-రాబాదు");
+}
+
+/// Test that all included dictionaries with non-ASCII patterns give
+/// consistent positions.
+#[test]
+fn test_non_ascii_dictionaries() {
+    for (lang, filename) in dictionaries() {
+        let content = fs::read_to_string(&filename).unwrap();
+        if content.is_ascii() {
+            continue;
+        }
+
+        let dic = Builder::lang(&lang).build().unwrap();
+        for word in sample_words(&content) {
+            let graphemes: Vec<_> = word.grapheme_indices(true).map(|(i, _)| i).collect();
+            let positions = dic.positions(&word);
+
+            let mut previous = 0;
+            for position in &positions {
+                assert!(position.value > previous, "{} {}", lang, word);
+                previous = position.value;
+
+                let offset = position.byte_offset(&word);
+                let index = graphemes.binary_search(&offset);
+                assert!(index.is_ok(), "{} {} {}", lang, word, offset);
+                let index = index.unwrap();
+                assert!(index >= dic.left(), "{} {}", lang, word);
+                assert!(index + dic.right() <= graphemes.len(), "{} {}", lang, word);
+            }
+
+            if positions.iter().all(|position| position.data.is_none()) {
+                assert_eq!(
+                    dic.inserted_with(&word, "\u{ad}").replace('\u{ad}', ""),
+                    word
+                );
+                for (first, second) in dic.iterate(&word) {
+                    assert_eq!(first.to_string() + &second, word, "{}", lang);
+                }
+            } else {
+                assert_eq!(dic.iterate(&word).count(), positions.len());
+            }
+        }
+    }
+}
+
+/// Test that compiled dictionaries give the same positions as the included
+/// dictionaries.
+#[test]
+fn test_compiled_dictionaries() {
+    for (lang, filename) in dictionaries() {
+        let content = fs::read_to_string(&filename).unwrap();
+        let hd = HyphDict::new(&filename).unwrap();
+        let compiled = HyphDict::from_compiled(hd.compile(), &filename).unwrap();
+        assert_eq!(compiled.metadata, hd.metadata, "{}", lang);
+
+        let dic = Builder::lang(&lang).build().unwrap();
+        let compiled = Builder::dictionary(Arc::new(compiled)).build().unwrap();
+        for word in sample_words(&content) {
+            assert_eq!(compiled.positions(&word), dic.positions(&word), "{}", lang);
+            assert_eq!(compiled.inserted(&word), dic.inserted(&word), "{}", lang);
+        }
+    }
+}
+
+/// Test that the included dictionaries are written back without loss.
+#[test]
+fn test_exported_dictionaries() {
+    for (lang, filename) in dictionaries() {
+        let content = fs::read_to_string(&filename).unwrap();
+        let hd = HyphDict::new(&filename).unwrap();
+        let dic = HyphDict::read(hd.to_dic().as_bytes(), &filename).unwrap();
+        assert_eq!(dic.metadata, hd.metadata, "{}", lang);
+        assert!(dic.compile() == hd.compile(), "{}", lang);
+        for word in sample_words(&content) {
+            assert_eq!(dic.positions(&word), hd.positions(&word), "{}", lang);
+        }
+
+        let tex = hd.to_tex();
+        let hd = HyphDict::from_tex(tex.as_bytes(), &filename).unwrap();
+        assert_eq!(hd.metadata.left_hyphen_min, dic.metadata.left_hyphen_min);
+        assert_eq!(hd.metadata.right_hyphen_min, dic.metadata.right_hyphen_min);
+        assert_eq!(hd.to_tex(), tex, "{}", lang);
+    }
+}
+
+/// Test that the included dictionaries have no problems changing how they
+/// are read.
+#[test]
+fn test_validated_dictionaries() {
+    let mut filenames: Vec<_> = dictionaries()
+        .into_iter()
+        .map(|(_, filename)| filename)
+        .collect();
+    filenames.sort();
+    filenames.dedup();
+
+    for filename in filenames {
+        let content = fs::read(&filename).unwrap();
+        for lint in validate(&content[..], &filename).unwrap() {
+            match lint.kind {
+                LintKind::InvalidEncoding
+                | LintKind::UnknownDirective { .. }
+                | LintKind::InvalidDirective { .. }
+                | LintKind::MalformedAlternative
+                | LintKind::MixedCase => panic!("{}", lint),
+                _ => (),
+            }
+        }
+    }
+}